@@ -0,0 +1,208 @@
+//! Run-length record of which flat-tree indices are present locally.
+
+use super::{flat, BitfieldError, NodeStore};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// A set of flat-tree indices, such as the blocks and nodes a sparse reader
+/// holds.
+///
+/// Indices are kept as sorted runs, so a replica that holds long stretches of
+/// the tree takes little space no matter how large the tree is. Blocks are
+/// the even indices, and block queries take block numbers rather than
+/// flat-tree indices. Indices past `Bitfield::MAX_INDEX` are never set.
+///
+/// ## Example
+/// ```rust
+/// use merkle_tree_stream::Bitfield;
+///
+/// let mut bitfield = Bitfield::new();
+/// bitfield.set_range(0..7, true);
+/// bitfield.set(10, true);
+///
+/// assert!(bitfield.has_all(0..4));
+/// assert!(!bitfield.has_all(0..6));
+/// assert_eq!(bitfield.next_missing(0), 4);
+/// assert_eq!(bitfield.next_missing(5), 6);
+///
+/// let bytes = bitfield.to_bytes();
+/// assert_eq!(Bitfield::from_bytes(&bytes), Ok(bitfield));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bitfield {
+  runs: Vec<Range<u64>>,
+}
+
+impl Bitfield {
+  /// The largest index a bitfield can hold, the same bound the rest of the
+  /// crate puts on flat-tree indices.
+  pub const MAX_INDEX: u64 = flat::MAX_INDEX;
+
+  /// Create a new, empty instance.
+  pub fn new() -> Self {
+    Self { runs: Vec::new() }
+  }
+
+  /// Create an instance that holds the index of every node in `store`.
+  pub fn from_store<N, S: NodeStore<N>>(store: &S) -> Result<Self, S::Error> {
+    let mut bitfield = Self::new();
+    for index in 0..store.len() {
+      if store.get(index)?.is_some() {
+        bitfield.set(index, true);
+      }
+    }
+    Ok(bitfield)
+  }
+
+  /// Check if `index` is set.
+  pub fn get(&self, index: u64) -> bool {
+    self.run(index).is_some()
+  }
+
+  /// Set or clear `index`. Does nothing if `index` is past `MAX_INDEX`.
+  pub fn set(&mut self, index: u64, value: bool) {
+    self.set_range(index..index.saturating_add(1), value)
+  }
+
+  /// Set or clear every index in `range`, ignoring any past `MAX_INDEX`.
+  pub fn set_range(&mut self, range: Range<u64>, value: bool) {
+    let start = range.start;
+    let end = range.end.min(Self::MAX_INDEX + 1);
+    if start >= end {
+      return;
+    }
+
+    if value {
+      // Merge every run that overlaps or touches the range.
+      let low = self.runs.partition_point(|run| run.end < start);
+      let high = self.runs.partition_point(|run| run.start <= end);
+      let merged = if low < high {
+        self.runs[low].start.min(start)..self.runs[high - 1].end.max(end)
+      } else {
+        start..end
+      };
+      self.runs.splice(low..high, Some(merged));
+    } else {
+      // Cut the range out of every run that overlaps it.
+      let low = self.runs.partition_point(|run| run.end <= start);
+      let high = self.runs.partition_point(|run| run.start < end);
+      let mut rest = Vec::new();
+      if low < high {
+        if self.runs[low].start < start {
+          rest.push(self.runs[low].start..start);
+        }
+        if self.runs[high - 1].end > end {
+          rest.push(end..self.runs[high - 1].end);
+        }
+      }
+      self.runs.splice(low..high, rest);
+    }
+  }
+
+  /// Get one past the highest index that is set, or zero if none is.
+  pub fn len(&self) -> u64 {
+    self.runs.last().map_or(0, |run| run.end)
+  }
+
+  /// Check if no index is set.
+  pub fn is_empty(&self) -> bool {
+    self.runs.is_empty()
+  }
+
+  /// Check if the leaf of `block` is set.
+  pub fn has_block(&self, block: u64) -> bool {
+    flat::checked_leaf(block).is_some_and(|index| self.get(index))
+  }
+
+  /// Check if the leaf of every block in `blocks` is set.
+  pub fn has_all(&self, blocks: Range<u64>) -> bool {
+    blocks.start >= blocks.end || self.next_missing(blocks.start) >= blocks.end
+  }
+
+  /// Get the first block at or after `block` whose leaf isn't set.
+  pub fn next_missing(&self, block: u64) -> u64 {
+    let mut index = match flat::checked_leaf(block) {
+      Some(index) => index,
+      None => return block,
+    };
+    while let Some(run) = self.run(index) {
+      // Runs never touch, so the first index after a run is clear. Runs end
+      // by `MAX_INDEX + 1`, so this can't overflow.
+      index = run.end + run.end % 2;
+    }
+    index / 2
+  }
+
+  /// Get the sorted, non-overlapping runs of set indices.
+  pub fn runs(&self) -> &[Range<u64>] {
+    &self.runs
+  }
+
+  /// Serialize the bitfield.
+  ///
+  /// Every run is written as the number of clear indices before it followed
+  /// by its length, both as LEB128 varints.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut previous = 0;
+    for run in &self.runs {
+      write_varint(&mut bytes, run.start - previous);
+      write_varint(&mut bytes, run.end - run.start);
+      previous = run.end;
+    }
+    bytes
+  }
+
+  /// Read a bitfield written by `to_bytes`.
+  pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, BitfieldError> {
+    let mut runs: Vec<Range<u64>> = Vec::new();
+    let mut previous = 0u64;
+    while !bytes.is_empty() {
+      let gap = read_varint(&mut bytes)?;
+      let len = read_varint(&mut bytes)?;
+      if len == 0 || (gap == 0 && !runs.is_empty()) {
+        return Err(BitfieldError::EmptyRun);
+      }
+
+      let start = previous.checked_add(gap).ok_or(BitfieldError::Overflow)?;
+      let end = start
+        .checked_add(len)
+        .filter(|&end| end <= Self::MAX_INDEX + 1)
+        .ok_or(BitfieldError::Overflow)?;
+      runs.push(start..end);
+      previous = end;
+    }
+    Ok(Self { runs })
+  }
+
+  /// Find the run that contains `index`.
+  fn run(&self, index: u64) -> Option<&Range<u64>> {
+    let next = self.runs.partition_point(|run| run.end <= index);
+    self.runs.get(next).filter(|run| run.start <= index)
+  }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+  while value >= 0x80 {
+    bytes.push(value as u8 | 0x80);
+    value >>= 7;
+  }
+  bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, BitfieldError> {
+  let mut value = 0u64;
+  for (i, &byte) in bytes.iter().enumerate() {
+    // The tenth byte only has room for the top bit of a `u64`.
+    if i == 9 && byte > 1 {
+      return Err(BitfieldError::Overflow);
+    }
+
+    value |= u64::from(byte & 0x7f) << (7 * i);
+    if byte & 0x80 == 0 {
+      *bytes = &bytes[i + 1..];
+      return Ok(value);
+    }
+  }
+  Err(BitfieldError::Truncated)
+}
@@ -0,0 +1,114 @@
+//! Binary encoding of the state of a `MerkleTreeStream`.
+//!
+//! A checkpoint is laid out as follows, with all integers big-endian:
+//!
+//! - the magic bytes `MTSC`
+//! - a version byte, currently `1`
+//! - the number of blocks as a `u64`
+//! - the number of roots as a `u32`
+//! - for every root: its index and length as `u64`s, the length of its hash
+//!   as a `u32`, and the hash itself
+
+use super::{flat, CheckpointError, Node, RootsError};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+const MAGIC: &[u8; 4] = b"MTSC";
+const VERSION: u8 = 1;
+
+/// A root as read from a checkpoint.
+pub(crate) struct Root<'a> {
+  pub(crate) index: u64,
+  pub(crate) length: u64,
+  pub(crate) hash: &'a [u8],
+}
+
+/// Encode `blocks` and `roots` into a checkpoint.
+pub(crate) fn encode<N: Node>(blocks: u64, roots: &[Arc<N>]) -> Vec<u8> {
+  let size: usize = roots.iter().map(|root| 20 + root.hash().len()).sum();
+  let mut buf = Vec::with_capacity(17 + size);
+
+  buf.extend_from_slice(MAGIC);
+  buf.push(VERSION);
+  buf.extend_from_slice(&blocks.to_be_bytes());
+  buf.extend_from_slice(&(roots.len() as u32).to_be_bytes());
+
+  for root in roots {
+    buf.extend_from_slice(&root.index().to_be_bytes());
+    buf.extend_from_slice(&root.len().to_be_bytes());
+    buf.extend_from_slice(&(root.hash().len() as u32).to_be_bytes());
+    buf.extend_from_slice(root.hash());
+  }
+
+  buf
+}
+
+/// Decode a checkpoint into its block count and roots. Only the format is
+/// checked here, not whether the roots make up a valid tree.
+pub(crate) fn decode(
+  bytes: &[u8],
+) -> Result<(u64, Vec<Root<'_>>), CheckpointError> {
+  let mut reader = Reader { bytes };
+
+  if reader.take(MAGIC.len())? != MAGIC {
+    return Err(CheckpointError::BadMagic);
+  }
+
+  let version = reader.take(1)?[0];
+  if version != VERSION {
+    return Err(CheckpointError::UnsupportedVersion(version));
+  }
+
+  let blocks = reader.u64()?;
+  let count = reader.u32()?;
+
+  // Don't trust `count` with an allocation, every root takes 20 bytes.
+  let mut roots = Vec::with_capacity((count as usize).min(bytes.len() / 20));
+  for _ in 0..count {
+    let index = reader.u64()?;
+    if index > flat::MAX_INDEX {
+      return Err(RootsError::IndexTooLarge { index }.into());
+    }
+    let length = reader.u64()?;
+    let hash_len = reader.u32()?;
+    let hash = reader.take(hash_len as usize)?;
+    roots.push(Root {
+      index,
+      length,
+      hash,
+    });
+  }
+
+  if !reader.bytes.is_empty() {
+    return Err(CheckpointError::TrailingBytes);
+  }
+
+  Ok((blocks, roots))
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, len: usize) -> Result<&'a [u8], CheckpointError> {
+    if self.bytes.len() < len {
+      return Err(CheckpointError::Truncated);
+    }
+
+    let (head, rest) = self.bytes.split_at(len);
+    self.bytes = rest;
+    Ok(head)
+  }
+
+  fn u64(&mut self) -> Result<u64, CheckpointError> {
+    let bytes = self.take(8)?;
+    Ok(u64::from_be_bytes(bytes.try_into().unwrap()))
+  }
+
+  fn u32(&mut self) -> Result<u32, CheckpointError> {
+    let bytes = self.take(4)?;
+    Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+  }
+}
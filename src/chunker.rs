@@ -0,0 +1,278 @@
+//! Strategies for splitting a byte stream into blocks.
+//!
+//! `FixedSize` cuts the input every `n` bytes. `FastCdc` and `Rabin` pick
+//! block boundaries based on the content itself, so inserting or removing
+//! bytes only changes the blocks around the edit: identical regions of two
+//! inputs end up as identical blocks, and therefore as identical leaf hashes.
+//!
+//! Chunkers are used through `MerkleWriter::with_chunker`, or directly on a
+//! complete input with `Chunker::chunks`.
+
+/// A strategy for splitting a byte stream into blocks.
+pub trait Chunker {
+  /// Get the largest block this chunker produces.
+  fn max_size(&self) -> usize;
+
+  /// Get the length of the first block in `data`.
+  ///
+  /// `data` is either at least `max_size()` bytes long, or contains everything
+  /// that's left of the input. The result must be between `1` and
+  /// `data.len()` for non-empty input.
+  fn cut(&self, data: &[u8]) -> usize;
+
+  /// Split a complete input into blocks.
+  fn chunks<'a>(&'a self, data: &'a [u8]) -> Chunks<'a, Self>
+  where
+    Self: Sized,
+  {
+    Chunks {
+      chunker: self,
+      data,
+    }
+  }
+}
+
+/// Iterator over the blocks of a complete input, created by
+/// `Chunker::chunks`.
+#[derive(Debug)]
+pub struct Chunks<'a, C> {
+  chunker: &'a C,
+  data: &'a [u8],
+}
+
+impl<'a, C: Chunker> Iterator for Chunks<'a, C> {
+  type Item = &'a [u8];
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.data.is_empty() {
+      return None;
+    }
+
+    let len = clamp_cut(self.chunker, self.data);
+    let (chunk, rest) = self.data.split_at(len);
+    self.data = rest;
+    Some(chunk)
+  }
+}
+
+/// Call `Chunker::cut`, making sure the result makes progress and stays in
+/// bounds.
+pub(crate) fn clamp_cut<C: Chunker>(chunker: &C, data: &[u8]) -> usize {
+  chunker.cut(data).max(1).min(data.len())
+}
+
+/// Split the input into blocks of `size` bytes. The last block may be
+/// shorter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedSize {
+  size: usize,
+}
+
+impl FixedSize {
+  /// Create a new instance.
+  ///
+  /// ## Panics
+  /// If `size` is zero.
+  pub fn new(size: usize) -> Self {
+    assert!(size > 0, "block size must be larger than zero");
+    Self { size }
+  }
+
+  /// Get the block size.
+  pub fn size(&self) -> usize {
+    self.size
+  }
+}
+
+impl Chunker for FixedSize {
+  fn max_size(&self) -> usize {
+    self.size
+  }
+
+  fn cut(&self, data: &[u8]) -> usize {
+    self.size.min(data.len())
+  }
+}
+
+/// Content-defined chunking with FastCDC.
+///
+/// Uses a gear rolling hash with normalized chunking: boundaries are harder to
+/// hit before `avg_size` bytes and easier after, which keeps block sizes
+/// close to the average. Blocks are never shorter than `min_size` (except
+/// the last one) nor longer than `max_size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FastCdc {
+  min_size: usize,
+  avg_size: usize,
+  max_size: usize,
+  mask_small: u64,
+  mask_large: u64,
+}
+
+impl FastCdc {
+  /// Create a new instance.
+  ///
+  /// ## Panics
+  /// Unless `0 < min_size <= avg_size <= max_size`.
+  pub fn new(min_size: usize, avg_size: usize, max_size: usize) -> Self {
+    assert_sizes(min_size, avg_size, max_size);
+    let bits = log2(avg_size);
+
+    Self {
+      min_size,
+      avg_size,
+      max_size,
+      mask_small: mask(bits + 2),
+      mask_large: mask(bits.saturating_sub(2)),
+    }
+  }
+}
+
+impl Chunker for FastCdc {
+  fn max_size(&self) -> usize {
+    self.max_size
+  }
+
+  fn cut(&self, data: &[u8]) -> usize {
+    if data.len() <= self.min_size {
+      return data.len();
+    }
+
+    let end = data.len().min(self.max_size);
+    let normal = end.min(self.avg_size);
+    let mut hash = 0u64;
+
+    for (i, byte) in data.iter().enumerate().take(end).skip(self.min_size) {
+      hash = (hash << 1).wrapping_add(GEAR[*byte as usize]);
+      let mask = if i < normal {
+        self.mask_small
+      } else {
+        self.mask_large
+      };
+
+      if hash & mask == 0 {
+        return i + 1;
+      }
+    }
+
+    end
+  }
+}
+
+/// Content-defined chunking with a Rabin-Karp rolling hash.
+///
+/// Hashes a sliding window of 48 bytes as a polynomial over the integers
+/// modulo 2^64, and places a boundary wherever the top bits of the hash are
+/// zero. Blocks are never shorter than `min_size` (except the last one) nor
+/// longer than `max_size`, and average roughly `avg_size` bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rabin {
+  min_size: usize,
+  max_size: usize,
+  mask: u64,
+}
+
+impl Rabin {
+  /// Create a new instance.
+  ///
+  /// ## Panics
+  /// Unless `0 < min_size <= avg_size <= max_size`.
+  pub fn new(min_size: usize, avg_size: usize, max_size: usize) -> Self {
+    assert_sizes(min_size, avg_size, max_size);
+
+    Self {
+      min_size,
+      max_size,
+      mask: mask(log2((avg_size - min_size).max(1))),
+    }
+  }
+}
+
+const RABIN_WINDOW: usize = 48;
+const RABIN_PRIME: u64 = 0x0100_0000_01b3;
+const RABIN_OUT: u64 = wrapping_pow(RABIN_PRIME, RABIN_WINDOW as u32);
+
+impl Chunker for Rabin {
+  fn max_size(&self) -> usize {
+    self.max_size
+  }
+
+  fn cut(&self, data: &[u8]) -> usize {
+    if data.len() <= self.min_size {
+      return data.len();
+    }
+
+    let end = data.len().min(self.max_size);
+    let start = self.min_size.saturating_sub(RABIN_WINDOW);
+    let mut hash = 0u64;
+
+    for i in start..end {
+      hash = hash
+        .wrapping_mul(RABIN_PRIME)
+        .wrapping_add(u64::from(data[i]) + 1);
+      if i >= start + RABIN_WINDOW {
+        let out = u64::from(data[i - RABIN_WINDOW]) + 1;
+        hash = hash.wrapping_sub(out.wrapping_mul(RABIN_OUT));
+      }
+
+      if i >= self.min_size && hash & self.mask == 0 {
+        return i + 1;
+      }
+    }
+
+    end
+  }
+}
+
+fn assert_sizes(min_size: usize, avg_size: usize, max_size: usize) {
+  assert!(
+    0 < min_size && min_size <= avg_size && avg_size <= max_size,
+    "chunk sizes must satisfy 0 < min <= avg <= max, got {} {} {}",
+    min_size,
+    avg_size,
+    max_size
+  );
+}
+
+/// Floor of the base 2 logarithm, `n` must be positive.
+fn log2(n: usize) -> u32 {
+  usize::BITS - 1 - n.leading_zeros()
+}
+
+/// Mask selecting the `bits` most significant bits of a `u64`.
+fn mask(bits: u32) -> u64 {
+  match bits {
+    0 => 0,
+    64..=u32::MAX => u64::MAX,
+    bits => u64::MAX << (64 - bits),
+  }
+}
+
+const fn wrapping_pow(base: u64, exp: u32) -> u64 {
+  let mut result = 1u64;
+  let mut i = 0;
+  while i < exp {
+    result = result.wrapping_mul(base);
+    i += 1;
+  }
+  result
+}
+
+/// Random values for the gear hash, generated with SplitMix64 so the table
+/// doesn't have to be spelled out.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+  let mut table = [0u64; 256];
+  let mut state = 0x6a09_e667_f3bc_c908u64;
+  let mut i = 0;
+  while i < 256 {
+    state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    table[i] = z ^ (z >> 31);
+    i += 1;
+  }
+  table
+}
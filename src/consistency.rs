@@ -0,0 +1,258 @@
+use super::{
+  blocks_from_roots, flat, parent_node, HashMethods, Node, NodeStore,
+};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::slice;
+
+/// Proof that a tree is an append-only extension of an earlier, shorter tree.
+///
+/// Holds the hashes of the subtrees that were appended after the first
+/// `old_blocks()` blocks. Together with the old roots they are enough to
+/// recompute every new root, which is how a client detects that a log it saw
+/// before has been forked.
+///
+/// ## Example
+/// ```rust
+/// # use merkle_tree_stream::{DefaultNode, HashMethods, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// # use std::sync::Arc;
+/// # struct XorHashMethods;
+/// # impl HashMethods for XorHashMethods {
+/// #   type Node = DefaultNode;
+/// #   type Hash = Vec<u8>;
+/// #   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+/// #     match leaf.data() {
+/// #       NodeKind::Parent => vec![0],
+/// #       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+/// #     }
+/// #   }
+/// #   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+/// #     vec![Node::hash(a).iter().chain(Node::hash(b).iter()).fold(0, |acc, x| acc ^ x)]
+/// #   }
+/// # }
+/// use merkle_tree_stream::ConsistencyProof;
+///
+/// let mut mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+/// let mut nodes = Vec::new();
+/// mts.next(b"hello", &mut nodes);
+/// let old_roots = mts.roots().clone();
+///
+/// mts.next(b"hashed", &mut nodes);
+/// mts.next(b"world", &mut nodes);
+///
+/// let proof = ConsistencyProof::new(1, mts.roots(), &nodes).unwrap();
+/// assert!(proof.verify(&XorHashMethods, &old_roots, mts.roots()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsistencyProof<N> {
+  old_blocks: u64,
+  new_blocks: u64,
+  nodes: Vec<Arc<N>>,
+}
+
+impl<N: Node> ConsistencyProof<N> {
+  /// Build a proof that the tree described by `new_roots` extends the tree
+  /// made up of its first `old_blocks` blocks, looking up the appended
+  /// subtrees in the `nodes` emitted by `MerkleTreeStream::next`.
+  ///
+  /// Returns `None` if `old_blocks` is larger than the new tree, or if a node
+  /// needed for the proof can't be found in `nodes`.
+  pub fn new(
+    old_blocks: u64,
+    new_roots: &[Arc<N>],
+    nodes: &[Arc<N>],
+  ) -> Option<Self> {
+    let found = Self::build(old_blocks, new_roots, &mut |index| {
+      let node = nodes.iter().find(|node| node.index() == index);
+      Ok::<_, Infallible>(node.map(Arc::clone))
+    });
+
+    match found {
+      Ok(proof) => proof,
+      Err(err) => match err {},
+    }
+  }
+
+  /// Build a proof that the tree described by `new_roots` extends the tree
+  /// made up of its first `old_blocks` blocks, looking up the appended
+  /// subtrees in `store`.
+  ///
+  /// Returns `Ok(None)` if `old_blocks` is larger than the new tree, or if a
+  /// node needed for the proof isn't in the store.
+  pub fn from_store<S: NodeStore<N>>(
+    old_blocks: u64,
+    new_roots: &[Arc<N>],
+    store: &S,
+  ) -> Result<Option<Self>, S::Error> {
+    Self::build(old_blocks, new_roots, &mut |index| store.get(index))
+  }
+
+  fn build<E>(
+    old_blocks: u64,
+    new_roots: &[Arc<N>],
+    get: &mut impl FnMut(u64) -> Result<Option<Arc<N>>, E>,
+  ) -> Result<Option<Self>, E> {
+    let new_blocks = blocks_from_roots(new_roots);
+    if old_blocks > new_blocks {
+      return Ok(None);
+    }
+
+    let mut proof = Vec::new();
+    for root in new_roots {
+      if !collect(root.index(), 2 * old_blocks, get, &mut proof)? {
+        return Ok(None);
+      }
+    }
+
+    Ok(Some(Self {
+      old_blocks,
+      new_blocks,
+      nodes: proof,
+    }))
+  }
+
+  /// Create a proof out of parts received from a peer, such as the lengths
+  /// and the appended subtrees of a proof built with `new`.
+  ///
+  /// The parts aren't checked until `verify` is called.
+  pub fn from_parts(
+    old_blocks: u64,
+    new_blocks: u64,
+    nodes: Vec<Arc<N>>,
+  ) -> Self {
+    Self {
+      old_blocks,
+      new_blocks,
+      nodes,
+    }
+  }
+
+  /// Get the number of blocks in the old tree.
+  pub fn old_blocks(&self) -> u64 {
+    self.old_blocks
+  }
+
+  /// Get the number of blocks in the new tree.
+  pub fn new_blocks(&self) -> u64 {
+    self.new_blocks
+  }
+
+  /// Get the appended subtrees, ordered from left to right.
+  pub fn nodes(&self) -> &[Arc<N>] {
+    &self.nodes
+  }
+
+  /// Get the flat-tree indices of the appended subtrees.
+  pub fn indices(&self) -> impl Iterator<Item = u64> + '_ {
+    self.nodes.iter().map(|node| node.index())
+  }
+
+  /// Check that `new_roots` describe an append-only extension of the tree
+  /// described by `old_roots`.
+  ///
+  /// The lengths and nodes of the proof aren't trusted: a proof whose lengths
+  /// don't match the roots, or whose nodes aren't the appended subtrees,
+  /// returns `false`.
+  pub fn verify<H>(
+    &self,
+    handler: &H,
+    old_roots: &[Arc<N>],
+    new_roots: &[Arc<N>],
+  ) -> bool
+  where
+    H: HashMethods<Node = N>,
+  {
+    if self.old_blocks > self.new_blocks
+      || flat::checked_leaf(self.new_blocks).is_none()
+      || !is_full_roots(old_roots, self.old_blocks)
+      || !is_full_roots(new_roots, self.new_blocks)
+    {
+      return false;
+    }
+
+    let old_end = 2 * self.old_blocks;
+    let mut proof = self.nodes.iter();
+    let roots_match = new_roots.iter().all(|root| {
+      match rebuild(handler, root.index(), old_end, old_roots, &mut proof) {
+        Some(node) => node.len() == root.len() && node.hash() == root.hash(),
+        None => false,
+      }
+    });
+
+    roots_match && proof.next().is_none()
+  }
+}
+
+/// Collect the subtrees under `index` that lie entirely past `old_end`.
+/// Returns `false` if one of them can't be found.
+fn collect<N: Node, E>(
+  index: u64,
+  old_end: u64,
+  get: &mut impl FnMut(u64) -> Result<Option<Arc<N>>, E>,
+  proof: &mut Vec<Arc<N>>,
+) -> Result<bool, E> {
+  let (left, right) = flat::spans(index);
+  if right < old_end {
+    return Ok(true);
+  }
+
+  if left >= old_end {
+    return Ok(match get(index)? {
+      Some(node) => {
+        proof.push(node);
+        true
+      }
+      None => false,
+    });
+  }
+
+  let (left, right) = match flat::children(index) {
+    Some(children) => children,
+    None => return Ok(false),
+  };
+  Ok(
+    collect(left, old_end, get, proof)? && collect(right, old_end, get, proof)?,
+  )
+}
+
+/// Check that `roots` are exactly the full roots of a tree of `blocks` blocks.
+fn is_full_roots<N: Node>(roots: &[Arc<N>], blocks: u64) -> bool {
+  let mut indices = Vec::new();
+  flat::full_roots(2 * blocks, &mut indices);
+  roots.len() == indices.len()
+    && roots
+      .iter()
+      .zip(indices)
+      .all(|(root, index)| root.index() == index)
+}
+
+/// Recompute the node at `index` out of the old roots and the appended
+/// subtrees.
+fn rebuild<H: HashMethods>(
+  handler: &H,
+  index: u64,
+  old_end: u64,
+  old_roots: &[Arc<H::Node>],
+  proof: &mut slice::Iter<'_, Arc<H::Node>>,
+) -> Option<Arc<H::Node>> {
+  let (left, right) = flat::spans(index);
+  if right < old_end {
+    let root = old_roots.iter().find(|root| root.index() == index)?;
+    return Some(Arc::clone(root));
+  }
+
+  if left >= old_end {
+    let node = proof.next()?;
+    return if node.index() == index {
+      Some(Arc::clone(node))
+    } else {
+      None
+    };
+  }
+
+  let (left, right) = flat::children(index)?;
+  let left = rebuild(handler, left, old_end, old_roots, proof)?;
+  let right = rebuild(handler, right, old_end, old_roots, proof)?;
+  Some(Arc::new(parent_node(handler, &left, &right)))
+}
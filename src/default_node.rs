@@ -1,13 +1,14 @@
 use super::{Node, NodeKind, NodeParts, PartialNode};
-use std::ops::{Deref, DerefMut};
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
 
 /// Node representation.
 #[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Clone)]
-pub struct DefaultNode {
+pub struct DefaultNode<B = Vec<u8>> {
   /// Reference to this node's parent node.
   pub parent: u64,
   /// Data if it's a leaf node, nothing if it's a parent node.
-  pub data: Option<Vec<u8>>,
+  pub data: Option<B>,
   /// Hash of the data
   pub hash: Vec<u8>,
   /// Total size of all its child nodes combined.
@@ -16,9 +17,9 @@ pub struct DefaultNode {
   pub index: u64,
 }
 
-impl DefaultNode {
+impl<B: Clone> DefaultNode<B> {
   /// Convert a `PartialNode` to a `Node`.
-  pub fn from_partial(partial: &PartialNode, hash: Vec<u8>) -> Self {
+  pub fn from_partial(partial: &PartialNode<B>, hash: Vec<u8>) -> Self {
     let data = match partial.data() {
       NodeKind::Leaf(data) => Some(data.clone()),
       NodeKind::Parent => None,
@@ -34,7 +35,7 @@ impl DefaultNode {
   }
 }
 
-impl Node for DefaultNode {
+impl<B> Node for DefaultNode<B> {
   fn hash(&self) -> &[u8] {
     &self.hash
   }
@@ -56,20 +57,34 @@ impl Node for DefaultNode {
   }
 }
 
-impl From<NodeParts<Vec<u8>>> for DefaultNode {
-  fn from(parts: NodeParts<Vec<u8>>) -> DefaultNode {
-    DefaultNode::from_partial(&parts.node, parts.hash)
+impl<B> From<NodeParts<Vec<u8>, B>> for DefaultNode<B> {
+  fn from(parts: NodeParts<Vec<u8>, B>) -> DefaultNode<B> {
+    // Move the data over instead of going through `from_partial`, so leaves
+    // aren't copied.
+    let NodeParts { node, hash } = parts;
+    let data = match node.data {
+      NodeKind::Leaf(data) => Some(data),
+      NodeKind::Parent => None,
+    };
+
+    Self {
+      index: node.index,
+      parent: node.parent,
+      length: node.length,
+      data,
+      hash,
+    }
   }
 }
 
-impl Deref for DefaultNode {
-  type Target = Option<Vec<u8>>;
+impl<B> Deref for DefaultNode<B> {
+  type Target = Option<B>;
   fn deref(&self) -> &Self::Target {
     &self.data
   }
 }
 
-impl DerefMut for DefaultNode {
+impl<B> DerefMut for DefaultNode<B> {
   fn deref_mut(&mut self) -> &mut Self::Target {
     &mut self.data
   }
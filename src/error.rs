@@ -0,0 +1,270 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+/// Error returned when a set of roots doesn't describe a valid tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootsError {
+  /// The root doesn't start right where the previous root ended.
+  NotContiguous {
+    /// Flat-tree index of the offending root.
+    index: u64,
+    /// The block the root should have started at.
+    expected_block: u64,
+  },
+  /// The root doesn't span fewer blocks than the previous root.
+  NotDecreasing {
+    /// Flat-tree index of the offending root.
+    index: u64,
+  },
+  /// The root's `parent` doesn't match its position in the flat-tree.
+  WrongParent {
+    /// Flat-tree index of the offending root.
+    index: u64,
+    /// The parent stored in the root.
+    parent: u64,
+  },
+  /// The root's index is larger than any tree the crate can handle.
+  IndexTooLarge {
+    /// Flat-tree index of the offending root.
+    index: u64,
+  },
+  /// The roots don't cover the expected number of blocks.
+  WrongLength {
+    /// The number of blocks covered by the roots.
+    blocks: u64,
+    /// The expected number of blocks.
+    expected: u64,
+  },
+}
+
+impl fmt::Display for RootsError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RootsError::NotContiguous {
+        index,
+        expected_block,
+      } => write!(
+        f,
+        "root {} does not start at block {}",
+        index, expected_block
+      ),
+      RootsError::NotDecreasing { index } => {
+        write!(f, "root {} is not smaller than the root before it", index)
+      }
+      RootsError::WrongParent { index, parent } => {
+        write!(f, "root {} has the wrong parent {}", index, parent)
+      }
+      RootsError::IndexTooLarge { index } => {
+        write!(f, "root {} is out of range", index)
+      }
+      RootsError::WrongLength { blocks, expected } => {
+        write!(f, "roots cover {} blocks instead of {}", blocks, expected)
+      }
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl Error for RootsError {}
+
+/// Error returned when a checkpoint can't be restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointError {
+  /// The data doesn't start with the checkpoint magic bytes.
+  BadMagic,
+  /// The checkpoint was written by an unknown version of the format.
+  UnsupportedVersion(u8),
+  /// The data ends in the middle of the checkpoint.
+  Truncated,
+  /// There's data left after the last root.
+  TrailingBytes,
+  /// The stored block count doesn't match the roots.
+  BlocksMismatch {
+    /// The block count stored in the checkpoint.
+    blocks: u64,
+    /// The block count covered by the roots.
+    expected: u64,
+  },
+  /// The roots don't describe a valid tree.
+  Roots(RootsError),
+}
+
+impl fmt::Display for CheckpointError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      CheckpointError::BadMagic => write!(f, "not a checkpoint"),
+      CheckpointError::UnsupportedVersion(version) => {
+        write!(f, "unsupported checkpoint version {}", version)
+      }
+      CheckpointError::Truncated => write!(f, "checkpoint is truncated"),
+      CheckpointError::TrailingBytes => {
+        write!(f, "checkpoint has trailing bytes")
+      }
+      CheckpointError::BlocksMismatch { blocks, expected } => write!(
+        f,
+        "checkpoint has {} blocks but its roots cover {}",
+        blocks, expected
+      ),
+      CheckpointError::Roots(err) => write!(f, "invalid roots: {}", err),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl Error for CheckpointError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      CheckpointError::Roots(err) => Some(err),
+      _ => None,
+    }
+  }
+}
+
+impl From<RootsError> for CheckpointError {
+  fn from(err: RootsError) -> Self {
+    CheckpointError::Roots(err)
+  }
+}
+
+/// Error returned when a stream can't be truncated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TruncateError<E> {
+  /// The stream is shorter than the requested length.
+  TooLong {
+    /// The number of blocks in the stream.
+    blocks: u64,
+    /// The requested number of blocks.
+    new_blocks: u64,
+  },
+  /// A root of the shorter tree isn't in the store.
+  MissingNode {
+    /// Flat-tree index of the missing node.
+    index: u64,
+  },
+  /// The store failed to look up a node.
+  Store(E),
+}
+
+impl<E: fmt::Display> fmt::Display for TruncateError<E> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TruncateError::TooLong { blocks, new_blocks } => write!(
+        f,
+        "cannot truncate {} blocks to {} blocks",
+        blocks, new_blocks
+      ),
+      TruncateError::MissingNode { index } => {
+        write!(f, "node {} is not in the store", index)
+      }
+      TruncateError::Store(err) => write!(f, "store error: {}", err),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<E: Error + 'static> Error for TruncateError<E> {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      TruncateError::Store(err) => Some(err),
+      _ => None,
+    }
+  }
+}
+
+/// Error returned when a byte offset can't be looked up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeekError<E> {
+  /// A node on the path to the block isn't in the store.
+  MissingNode {
+    /// Flat-tree index of the missing node.
+    index: u64,
+  },
+  /// The store failed to look up a node.
+  Store(E),
+}
+
+impl<E: fmt::Display> fmt::Display for SeekError<E> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SeekError::MissingNode { index } => {
+        write!(f, "node {} is not in the store", index)
+      }
+      SeekError::Store(err) => write!(f, "store error: {}", err),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<E: Error + 'static> Error for SeekError<E> {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      SeekError::Store(err) => Some(err),
+      _ => None,
+    }
+  }
+}
+
+/// Error returned when a `Verifier` rejects a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+  /// The node isn't covered by any of the trusted roots.
+  OutOfBounds {
+    /// Flat-tree index of the node.
+    index: u64,
+  },
+  /// The nodes below a trusted node don't hash up to it, or a node was
+  /// received that differs from the trusted node at its index.
+  Mismatch {
+    /// Flat-tree index of the trusted node.
+    index: u64,
+  },
+  /// A different node is already waiting to be verified at this index.
+  Conflict {
+    /// Flat-tree index of the node.
+    index: u64,
+  },
+}
+
+impl fmt::Display for VerifyError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      VerifyError::OutOfBounds { index } => {
+        write!(f, "node {} is not covered by the trusted roots", index)
+      }
+      VerifyError::Mismatch { index } => {
+        write!(f, "nodes do not match trusted node {}", index)
+      }
+      VerifyError::Conflict { index } => {
+        write!(f, "a different node {} is already pending", index)
+      }
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl Error for VerifyError {}
+
+/// Error returned when a serialized `Bitfield` can't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitfieldError {
+  /// The data ends in the middle of a run.
+  Truncated,
+  /// A run is empty, or touches the run before it.
+  EmptyRun,
+  /// A run extends past `Bitfield::MAX_INDEX`.
+  Overflow,
+}
+
+impl fmt::Display for BitfieldError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BitfieldError::Truncated => write!(f, "bitfield is truncated"),
+      BitfieldError::EmptyRun => write!(f, "bitfield has an empty run"),
+      BitfieldError::Overflow => write!(f, "bitfield index overflows"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl Error for BitfieldError {}
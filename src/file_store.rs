@@ -0,0 +1,113 @@
+use super::{flat, DefaultNode, Node, NodeStore};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+
+/// A `NodeStore` that keeps `DefaultNode`s in a file.
+///
+/// Every node is written as a fixed-width record at `index * record_size()`:
+/// a presence flag, the node's length as a big-endian `u64`, and its hash.
+/// All hashes must be `hash_size` bytes long. Block data isn't stored, so
+/// nodes read back from the file have no `data`.
+#[derive(Debug)]
+pub struct FileStore {
+  file: File,
+  hash_size: usize,
+}
+
+impl FileStore {
+  /// Create a new instance that stores hashes of `hash_size` bytes in `file`.
+  ///
+  /// The file must be opened for reading and writing. Existing records are
+  /// kept, so a store can be reopened.
+  pub fn new(file: File, hash_size: usize) -> Self {
+    Self { file, hash_size }
+  }
+
+  /// Get the size of a single record in bytes.
+  pub fn record_size(&self) -> usize {
+    1 + 8 + self.hash_size
+  }
+
+  /// Get the hash size.
+  pub fn hash_size(&self) -> usize {
+    self.hash_size
+  }
+
+  /// Get the underlying file back.
+  pub fn into_inner(self) -> File {
+    self.file
+  }
+
+  fn offset(&self, index: u64) -> io::Result<u64> {
+    index.checked_mul(self.record_size() as u64).ok_or_else(|| {
+      io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("index {} is past the largest file offset", index),
+      )
+    })
+  }
+
+  /// Get the number of records in the file.
+  fn records(&self) -> io::Result<u64> {
+    Ok(self.file.metadata()?.len() / self.record_size() as u64)
+  }
+}
+
+impl NodeStore<DefaultNode> for FileStore {
+  type Error = io::Error;
+
+  fn get(&self, index: u64) -> Result<Option<Arc<DefaultNode>>, Self::Error> {
+    if index >= self.records()? {
+      return Ok(None);
+    }
+
+    let mut record = vec![0; self.record_size()];
+    let mut file = &self.file;
+    file.seek(SeekFrom::Start(self.offset(index)?))?;
+    file.read_exact(&mut record)?;
+
+    if record[0] == 0 {
+      return Ok(None);
+    }
+
+    let mut length = [0; 8];
+    length.copy_from_slice(&record[1..9]);
+
+    Ok(Some(Arc::new(DefaultNode {
+      index,
+      parent: flat::parent(index),
+      length: u64::from_be_bytes(length),
+      hash: record[9..].to_vec(),
+      data: None,
+    })))
+  }
+
+  fn put(&mut self, node: Arc<DefaultNode>) -> Result<(), Self::Error> {
+    if node.hash().len() != self.hash_size {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+          "expected a hash of {} bytes, got {}",
+          self.hash_size,
+          node.hash().len()
+        ),
+      ));
+    }
+
+    let mut record = Vec::with_capacity(self.record_size());
+    record.push(1);
+    record.extend_from_slice(&node.len().to_be_bytes());
+    record.extend_from_slice(node.hash());
+
+    let offset = self.offset(node.index())?;
+    self.file.seek(SeekFrom::Start(offset))?;
+    self.file.write_all(&record)
+  }
+
+  fn len(&self) -> u64 {
+    // `len` can't fail, so a file whose size can't be read counts as empty.
+    // `get` reads the size itself and returns the error instead.
+    self.records().unwrap_or(0)
+  }
+}
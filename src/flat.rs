@@ -0,0 +1,95 @@
+//! The flat-tree functions used by this crate.
+//!
+//! These match the `flat-tree` crate, which depends on `std` and so can't be
+//! used in `no_std` builds.
+
+use alloc::vec::Vec;
+
+/// The largest index the crate works with. The parent, sibling and spans of
+/// every index up to it, and of its parent, fit in a `u64`, so untrusted
+/// indices are checked against it before doing any flat-tree math on them.
+pub(crate) const MAX_INDEX: u64 = (1 << 62) - 2;
+
+/// Get the index of the leaf of `block`, or `None` if it's past `MAX_INDEX`.
+pub(crate) fn checked_leaf(block: u64) -> Option<u64> {
+  block.checked_mul(2).filter(|&index| index <= MAX_INDEX)
+}
+
+/// Get the index of the node at `offset` on `depth`.
+fn index(depth: u64, offset: u64) -> u64 {
+  (1 + 2 * offset) * (1 << depth) - 1
+}
+
+/// Get the depth of a node, leaves are at depth 0.
+pub(crate) fn depth(i: u64) -> u64 {
+  (!i).trailing_zeros() as u64
+}
+
+/// Get the position of a node among the nodes on its depth.
+fn offset(i: u64) -> u64 {
+  if i & 1 == 0 {
+    i / 2
+  } else {
+    i >> (depth(i) + 1)
+  }
+}
+
+/// Get the index of a node's parent.
+pub(crate) fn parent(i: u64) -> u64 {
+  index(depth(i) + 1, offset(i) >> 1)
+}
+
+/// Get the index of a node's sibling.
+pub(crate) fn sibling(i: u64) -> u64 {
+  index(depth(i), offset(i) ^ 1)
+}
+
+/// Get the indices of a node's children, or `None` for a leaf.
+pub(crate) fn children(i: u64) -> Option<(u64, u64)> {
+  if i & 1 == 0 {
+    return None;
+  }
+
+  let depth = depth(i);
+  let offset = offset(i) * 2;
+  Some((index(depth - 1, offset), index(depth - 1, offset + 1)))
+}
+
+/// Get the index of the rightmost leaf under a node.
+pub(crate) fn right_span(i: u64) -> u64 {
+  match depth(i) {
+    0 => i,
+    depth => (offset(i) + 1) * (2 << depth) - 2,
+  }
+}
+
+/// Get the index of the leftmost leaf under a node.
+pub(crate) fn left_span(i: u64) -> u64 {
+  match depth(i) {
+    0 => i,
+    depth => offset(i) * (2 << depth),
+  }
+}
+
+/// Get the indices of the leftmost and rightmost leaves under a node.
+pub(crate) fn spans(i: u64) -> (u64, u64) {
+  (left_span(i), right_span(i))
+}
+
+/// Push the roots of the tree whose leaves end right before leaf `i` onto
+/// `nodes`.
+///
+/// ## Panics
+/// If `i` isn't a leaf.
+pub(crate) fn full_roots(i: u64, nodes: &mut Vec<u64>) {
+  assert!(i & 1 == 0, "full roots only exist for leaves, got {}", i);
+
+  let mut remaining = i >> 1;
+  let mut offset = 0;
+  while remaining > 0 {
+    let factor = 1 << (63 - remaining.leading_zeros());
+    nodes.push(offset + factor - 1);
+    offset += 2 * factor;
+    remaining -= factor;
+  }
+}
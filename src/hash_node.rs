@@ -0,0 +1,94 @@
+use super::{DefaultNode, Node, NodeParts};
+use alloc::vec::Vec;
+
+/// Node representation that only keeps the hash, never the block data.
+///
+/// Use it as `HashMethods::Node` when blocks are stored elsewhere, so the
+/// emitted nodes and the long-lived roots don't hold on to a copy of every
+/// leaf.
+///
+/// ## Example
+/// ```rust
+/// use merkle_tree_stream::{HashMethods, HashNode, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// use std::sync::Arc;
+///
+/// struct XorHashMethods;
+/// impl HashMethods for XorHashMethods {
+///   type Node = HashNode;
+///   type Hash = Vec<u8>;
+///
+///   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+///     match leaf.data() {
+///       NodeKind::Parent => vec![0],
+///       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+///     }
+///   }
+///
+///   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+///     vec![a.hash().iter().chain(b.hash().iter()).fold(0, |acc, x| acc ^ x)]
+///   }
+/// }
+///
+/// let mut mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+/// let mut nodes = Vec::new();
+/// mts.next(b"hello", &mut nodes);
+///
+/// assert_eq!(nodes[0].len(), 5);
+/// assert_eq!(mts.roots()[0].hash(), &nodes[0].hash[..]);
+/// ```
+#[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Clone)]
+pub struct HashNode {
+  /// Reference to this node's parent node.
+  pub parent: u64,
+  /// Hash of the data
+  pub hash: Vec<u8>,
+  /// Total size of all its child nodes combined.
+  pub length: u64,
+  /// Offset into the flat-tree data structure.
+  pub index: u64,
+}
+
+impl Node for HashNode {
+  fn hash(&self) -> &[u8] {
+    &self.hash
+  }
+
+  fn len(&self) -> u64 {
+    self.length
+  }
+
+  fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+
+  fn index(&self) -> u64 {
+    self.index
+  }
+
+  fn parent(&self) -> u64 {
+    self.parent
+  }
+}
+
+impl<B> From<NodeParts<Vec<u8>, B>> for HashNode {
+  fn from(parts: NodeParts<Vec<u8>, B>) -> HashNode {
+    let node = parts.node();
+    HashNode {
+      index: node.index(),
+      parent: node.parent,
+      length: node.len(),
+      hash: parts.hash,
+    }
+  }
+}
+
+impl<B> From<DefaultNode<B>> for HashNode {
+  fn from(node: DefaultNode<B>) -> HashNode {
+    HashNode {
+      index: node.index,
+      parent: node.parent,
+      length: node.length,
+      hash: node.hash,
+    }
+  }
+}
@@ -0,0 +1,102 @@
+//! Hypercore-compatible hashing.
+//!
+//! Implements the BLAKE2b-256 scheme used by Hypercore, so trees built with
+//! `MerkleTreeStream` are byte-compatible with the ones produced by the
+//! JavaScript `hypercore-crypto` package (2.x). Every hash is prefixed with a
+//! type byte (`0x00` for leaves, `0x01` for parents and `0x02` for the tree
+//! hash) and lengths are encoded as big-endian `u64`.
+//!
+//! Note that `hypercore-crypto` 3.x encodes lengths as little-endian instead,
+//! so its hashes differ from the ones produced here.
+
+use super::{DefaultNode, HashMethods, Node, NodeKind, PartialNode};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use std::sync::Arc;
+
+type Blake2b256 = Blake2b<U32>;
+
+const LEAF_TYPE: [u8; 1] = [0x00];
+const PARENT_TYPE: [u8; 1] = [0x01];
+const ROOT_TYPE: [u8; 1] = [0x02];
+
+/// `HashMethods` implementation using Hypercore's BLAKE2b-256 scheme.
+///
+/// ## Example
+/// ```rust
+/// use merkle_tree_stream::hypercore::Blake2bHashMethods;
+/// use merkle_tree_stream::MerkleTreeStream;
+///
+/// let mut mts = MerkleTreeStream::new(Blake2bHashMethods, Vec::new());
+/// let mut nodes = Vec::new();
+/// mts.next(b"hello world", &mut nodes);
+///
+/// assert_eq!(nodes[0].hash.len(), 32);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Blake2bHashMethods;
+
+impl HashMethods for Blake2bHashMethods {
+  type Node = DefaultNode;
+  type Hash = Vec<u8>;
+
+  fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+    match leaf.data() {
+      NodeKind::Leaf(data) => leaf_hash(data),
+      NodeKind::Parent => unreachable!("leaf called with a parent node"),
+    }
+  }
+
+  fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+    parent_hash(a, b)
+  }
+
+  fn tree(&self, roots: &[Arc<Self::Node>]) -> Option<Self::Hash> {
+    Some(tree_hash(roots))
+  }
+
+  fn leaf_uses_roots(&self) -> bool {
+    false
+  }
+}
+
+/// Hash a block of data the way Hypercore hashes its leaves.
+pub fn leaf_hash(data: &[u8]) -> Vec<u8> {
+  let mut hasher = Blake2b256::new();
+  hasher.update(LEAF_TYPE);
+  hasher.update((data.len() as u64).to_be_bytes());
+  hasher.update(data);
+  hasher.finalize().to_vec()
+}
+
+/// Hash two sibling nodes into their parent the way Hypercore does. The
+/// nodes may be passed in either order.
+pub fn parent_hash<N: Node>(a: &N, b: &N) -> Vec<u8> {
+  let (a, b) = if a.index() > b.index() {
+    (b, a)
+  } else {
+    (a, b)
+  };
+
+  let mut hasher = Blake2b256::new();
+  hasher.update(PARENT_TYPE);
+  // Lengths can come from untrusted peers. Saturate like `Weight for u64`,
+  // so the hashed length matches the one the parent node stores.
+  hasher.update(a.len().saturating_add(b.len()).to_be_bytes());
+  hasher.update(a.hash());
+  hasher.update(b.hash());
+  hasher.finalize().to_vec()
+}
+
+/// Fold a set of roots into a single digest, matching `crypto.tree()` in
+/// `hypercore-crypto`.
+pub fn tree_hash<N: Node>(roots: &[Arc<N>]) -> Vec<u8> {
+  let mut hasher = Blake2b256::new();
+  hasher.update(ROOT_TYPE);
+  for root in roots {
+    hasher.update(root.hash());
+    hasher.update(root.index().to_be_bytes());
+    hasher.update(root.len().to_be_bytes());
+  }
+  hasher.finalize().to_vec()
+}
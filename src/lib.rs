@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unsafe_code, missing_debug_implementations, missing_docs)]
 #![cfg_attr(test, deny(warnings))]
 
@@ -34,31 +35,80 @@
 //! mts.next(b"world", &mut nodes);
 //! ```
 
-extern crate flat_tree as flat;
+extern crate alloc;
 
+mod bitfield;
+mod checkpoint;
+mod consistency;
 mod default_node;
+mod error;
+#[cfg(feature = "std")]
+mod file_store;
+mod flat;
+mod hash_node;
+mod multiproof;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod partial_node;
+mod proof;
+mod range_proof;
+mod seek;
+mod sparse;
+mod store;
+mod subtree;
+mod verifier;
+mod weight;
 
+pub mod chunker;
+#[cfg(feature = "std")]
+pub mod writer;
+
+#[cfg(feature = "hypercore")]
+pub mod hypercore;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "futures")]
+pub mod sink;
+
+pub use crate::bitfield::Bitfield;
+pub use crate::consistency::ConsistencyProof;
 pub use crate::default_node::DefaultNode;
+pub use crate::error::{
+  BitfieldError, CheckpointError, RootsError, SeekError, TruncateError,
+  VerifyError,
+};
+#[cfg(feature = "std")]
+pub use crate::file_store::FileStore;
+pub use crate::hash_node::HashNode;
+pub use crate::multiproof::MultiProof;
 pub use crate::partial_node::{NodeKind, PartialNode};
+pub use crate::proof::Proof;
+pub use crate::range_proof::RangeProof;
+pub use crate::seek::seek;
+pub use crate::sparse::SparseTree;
+pub use crate::store::{MemoryStore, NodeStore};
+pub use crate::verifier::Verifier;
+pub use crate::weight::{Weight, Weighted};
 
-use std::sync::Arc;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::Infallible;
 
 /// The parts that make up a full Node from a PartialNode
 #[derive(Debug)]
-pub struct NodeParts<H> {
-  node: PartialNode,
+pub struct NodeParts<H, B = Vec<u8>, W = u64> {
+  node: PartialNode<B, W>,
   hash: H,
 }
 
-impl<H> NodeParts<H> {
+impl<H, B, W> NodeParts<H, B, W> {
   /// Access the `node` property.
-  pub fn node(&self) -> &PartialNode {
+  pub fn node(&self) -> &PartialNode<B, W> {
     &self.node
   }
 
   /// Access the `node` property mutably.
-  pub fn node_mut(&mut self) -> &mut PartialNode {
+  pub fn node_mut(&mut self) -> &mut PartialNode<B, W> {
     &mut self.node
   }
 
@@ -74,15 +124,96 @@ impl<H> NodeParts<H> {
 }
 
 /// Functions that need to be implemented for `MerkleTreeStream`.
-pub trait HashMethods {
+///
+/// `B` is the buffer type leaf data is held in, see `NodeKind`. `W` is the
+/// weight rolled up the tree, see `Weight`.
+pub trait HashMethods<B = Vec<u8>, W = u64> {
   /// The Node type we'll iterate over.
-  type Node: Node + From<NodeParts<Self::Hash>>;
+  type Node: Node + Weighted<W> + From<NodeParts<Self::Hash, B, W>>;
   /// The type of hash returned from the hashing functions.
   type Hash;
   /// Pass data through a hash function.
-  fn leaf(&self, leaf: &PartialNode, roots: &[Arc<Self::Node>]) -> Self::Hash;
+  fn leaf(
+    &self,
+    leaf: &PartialNode<B, W>,
+    roots: &[Arc<Self::Node>],
+  ) -> Self::Hash;
   /// Pass hashes through a hash function.
   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash;
+  /// Fold a set of roots into a single digest that summarizes the whole
+  /// tree. Returns `None` unless the hasher defines a tree hash.
+  fn tree(&self, _roots: &[Arc<Self::Node>]) -> Option<Self::Hash> {
+    None
+  }
+  /// Check if `leaf` reads its `roots` argument. Hashers that return `false`
+  /// let leaves be hashed without the roots, for example in parallel by
+  /// `MerkleTreeStream::par_extend`. Defaults to `true`.
+  fn leaf_uses_roots(&self) -> bool {
+    true
+  }
+}
+
+/// Fallible counterpart of `HashMethods`, for hash functions that can fail.
+///
+/// Every `HashMethods` implementation is also a `TryHashMethods` that never
+/// fails. Streams using a fallible hasher are driven with
+/// `MerkleTreeStream::try_next`.
+pub trait TryHashMethods<B = Vec<u8>, W = u64> {
+  /// The Node type we'll iterate over.
+  type Node: Node + Weighted<W> + From<NodeParts<Self::Hash, B, W>>;
+  /// The type of hash returned from the hashing functions.
+  type Hash;
+  /// The error returned when hashing fails.
+  type Error;
+  /// Pass data through a hash function.
+  fn try_leaf(
+    &self,
+    leaf: &PartialNode<B, W>,
+    roots: &[Arc<Self::Node>],
+  ) -> Result<Self::Hash, Self::Error>;
+  /// Pass hashes through a hash function.
+  fn try_parent(
+    &self,
+    a: &Self::Node,
+    b: &Self::Node,
+  ) -> Result<Self::Hash, Self::Error>;
+  /// Fold a set of roots into a single digest that summarizes the whole
+  /// tree. Returns `Ok(None)` unless the hasher defines a tree hash.
+  fn try_tree(
+    &self,
+    _roots: &[Arc<Self::Node>],
+  ) -> Result<Option<Self::Hash>, Self::Error> {
+    Ok(None)
+  }
+}
+
+impl<T: HashMethods<B, W>, B, W> TryHashMethods<B, W> for T {
+  type Node = T::Node;
+  type Hash = T::Hash;
+  type Error = Infallible;
+
+  fn try_leaf(
+    &self,
+    leaf: &PartialNode<B, W>,
+    roots: &[Arc<Self::Node>],
+  ) -> Result<Self::Hash, Self::Error> {
+    Ok(self.leaf(leaf, roots))
+  }
+
+  fn try_parent(
+    &self,
+    a: &Self::Node,
+    b: &Self::Node,
+  ) -> Result<Self::Hash, Self::Error> {
+    Ok(self.parent(a, b))
+  }
+
+  fn try_tree(
+    &self,
+    roots: &[Arc<Self::Node>],
+  ) -> Result<Option<Self::Hash>, Self::Error> {
+    Ok(self.tree(roots))
+  }
 }
 
 /// Functions that need to be implemented for the Data that `MerkleTreeStream`
@@ -175,22 +306,16 @@ pub trait Node {
 /// assert_eq!(mts.roots()[1].index, 4);
 /// ```
 #[derive(Debug)]
-pub struct MerkleTreeStream<T: HashMethods> {
+pub struct MerkleTreeStream<T: TryHashMethods<B, W>, B = Vec<u8>, W = u64> {
   handler: T,
   roots: Vec<Arc<T::Node>>,
   blocks: u64,
 }
 
-impl<H: HashMethods> MerkleTreeStream<H> {
+impl<H: TryHashMethods<B, W>, B, W: Weight> MerkleTreeStream<H, B, W> {
   /// Create a new MerkleTreeStream instance.
-  pub fn new(handler: H, roots: Vec<Arc<H::Node>>) -> MerkleTreeStream<H> {
-    let blocks = if !roots.is_empty() {
-      // Cant panic because roots.len() > 0
-      let root = roots.last().unwrap();
-      1 + flat::right_span(root.index()) / 2
-    } else {
-      0
-    };
+  pub fn new(handler: H, roots: Vec<Arc<H::Node>>) -> Self {
+    let blocks = blocks_from_roots(&roots);
 
     MerkleTreeStream {
       handler,
@@ -199,57 +324,134 @@ impl<H: HashMethods> MerkleTreeStream<H> {
     }
   }
 
-  /// Pass a string buffer through the flat-tree hash functions, and write the
-  /// result back out to "nodes".
-  pub fn next<'a>(&mut self, data: &[u8], nodes: &'a mut Vec<Arc<H::Node>>) {
+  /// Create a new MerkleTreeStream instance, checking that `roots` are the
+  /// full roots of a tree.
+  ///
+  /// Unlike `new`, which trusts its input, this returns an error if the roots
+  /// are out of order, leave gaps, overlap, or don't point at their
+  /// flat-tree parents. The byte lengths of the roots can't be checked
+  /// without the data, and are taken as-is.
+  pub fn from_roots(
+    handler: H,
+    roots: Vec<Arc<H::Node>>,
+  ) -> Result<Self, RootsError> {
+    validate_roots(&roots)?;
+    Ok(Self::new(handler, roots))
+  }
+
+  /// Like `next_owned`, but propagates errors from the hash functions.
+  ///
+  /// If hashing fails the stream is left exactly as it was: `roots`,
+  /// `blocks` and `nodes` are not modified.
+  pub fn try_next_owned(
+    &mut self,
+    data: B,
+    nodes: &mut Vec<Arc<H::Node>>,
+  ) -> Result<(), H::Error>
+  where
+    B: AsRef<[u8]>,
+  {
     let index: u64 = 2 * self.blocks;
-    self.blocks += 1;
 
     let leaf = PartialNode {
       index,
-      parent: flat::parent(index) as u64,
-      length: data.len() as u64,
-      data: NodeKind::Leaf(data.to_vec()),
+      parent: flat::parent(index),
+      length: W::leaf(data.as_ref()),
+      data: NodeKind::Leaf(data),
     };
 
-    let hash = self.handler.leaf(&leaf, &self.roots);
+    let hash = self.handler.try_leaf(&leaf, &self.roots)?;
     let parts = NodeParts { node: leaf, hash };
-    let node = Arc::new(H::Node::from(parts));
+    self.try_push_leaf(Arc::new(H::Node::from(parts)), nodes)
+  }
 
-    self.roots.push(Arc::clone(&node));
+  /// Append an already hashed leaf for the next block, merging it with the
+  /// roots and writing the new nodes out to "nodes".
+  pub(crate) fn try_push_leaf(
+    &mut self,
+    mut node: Arc<H::Node>,
+    nodes: &mut Vec<Arc<H::Node>>,
+  ) -> Result<(), H::Error> {
+    // Merge with the existing roots without modifying them, so that a
+    // failing hash doesn't leave the stream half updated.
+    let start = nodes.len();
     nodes.push(Arc::clone(&node));
 
-    while self.roots.len() > 1 {
-      let leaf = {
-        let left = &self.roots[self.roots.len() - 2];
-        let right = &self.roots[self.roots.len() - 1];
+    let mut merged = 0;
+    for left in self.roots.iter().rev() {
+      if left.parent() != node.parent() {
+        break;
+      }
 
-        if left.parent() != right.parent() {
-          break;
+      match try_parent_node::<H, B, W>(&self.handler, left, &node) {
+        Ok(parent) => node = Arc::new(parent),
+        Err(err) => {
+          nodes.truncate(start);
+          return Err(err);
         }
+      }
 
-        let hash = self.handler.parent(left, right);
-        let partial = PartialNode {
-          index: left.parent(),
-          parent: flat::parent(left.parent()) as u64,
-          length: left.len() + right.len(),
-          data: NodeKind::Parent,
-        };
+      nodes.push(Arc::clone(&node));
+      merged += 1;
+    }
 
-        H::Node::from(NodeParts {
-          node: partial,
-          hash,
-        })
-      };
+    self.roots.truncate(self.roots.len() - merged);
+    self.roots.push(node);
+    self.blocks += 1;
+    Ok(())
+  }
 
-      for _ in 0..2 {
-        self.roots.pop();
-      }
+  /// Roll the stream back to its first `new_blocks` blocks.
+  ///
+  /// The roots of the shorter tree are taken from the current roots where
+  /// possible, and looked up in `store` otherwise. If any of them can't be
+  /// found the stream is left unchanged.
+  pub fn truncate<S: NodeStore<H::Node>>(
+    &mut self,
+    new_blocks: u64,
+    store: &S,
+  ) -> Result<(), TruncateError<S::Error>> {
+    if new_blocks > self.blocks {
+      return Err(TruncateError::TooLong {
+        blocks: self.blocks,
+        new_blocks,
+      });
+    }
+
+    let mut indices = Vec::new();
+    flat::full_roots(2 * new_blocks, &mut indices);
 
-      let leaf = Arc::new(leaf);
-      self.roots.push(Arc::clone(&leaf));
-      nodes.push(Arc::clone(&leaf));
+    let mut roots = Vec::with_capacity(indices.len());
+    for index in indices {
+      let current = self.roots.iter().find(|root| root.index() == index);
+      let root = match current {
+        Some(root) => Arc::clone(root),
+        None => store
+          .get(index)
+          .map_err(TruncateError::Store)?
+          .ok_or(TruncateError::MissingNode { index })?,
+      };
+      roots.push(root);
     }
+
+    self.roots = roots;
+    self.blocks = new_blocks;
+    Ok(())
+  }
+
+  /// Find the block that contains `byte_offset`, looking up nodes below the
+  /// roots in `store`. See `seek` for details.
+  pub fn seek<S: NodeStore<H::Node>>(
+    &self,
+    byte_offset: u64,
+    store: &S,
+  ) -> Result<Option<(u64, u64)>, SeekError<S::Error>> {
+    seek(&self.roots, store, byte_offset)
+  }
+
+  /// Like `tree_hash`, but propagates errors from the hash functions.
+  pub fn try_tree_hash(&self) -> Result<Option<H::Hash>, H::Error> {
+    self.handler.try_tree(&self.roots)
   }
 
   /// Get the roots vector.
@@ -262,3 +464,257 @@ impl<H: HashMethods> MerkleTreeStream<H> {
     self.blocks
   }
 }
+
+impl<H: TryHashMethods<B>, B> MerkleTreeStream<H, B> {
+  /// Create a new MerkleTreeStream instance from a checkpoint written by
+  /// `checkpoint`.
+  ///
+  /// The checkpoint's roots are validated like in `from_roots`, and must
+  /// cover exactly the stored number of blocks. Only the index, length and
+  /// hash of every root is stored, so restored leaf roots have no data.
+  pub fn restore(handler: H, checkpoint: &[u8]) -> Result<Self, CheckpointError>
+  where
+    H::Hash: for<'a> From<&'a [u8]>,
+  {
+    let (blocks, stored) = checkpoint::decode(checkpoint)?;
+    let roots: Vec<_> = stored
+      .into_iter()
+      .map(|root| {
+        let node = PartialNode {
+          index: root.index,
+          parent: flat::parent(root.index),
+          length: root.length,
+          data: NodeKind::Parent,
+        };
+        let hash = H::Hash::from(root.hash);
+        Arc::new(H::Node::from(NodeParts { node, hash }))
+      })
+      .collect();
+
+    validate_roots(&roots)?;
+    let expected = blocks_from_roots(&roots);
+    if blocks != expected {
+      return Err(CheckpointError::BlocksMismatch { blocks, expected });
+    }
+
+    Ok(Self::new(handler, roots))
+  }
+
+  /// Encode the number of blocks and the current roots into a versioned
+  /// binary checkpoint, which `restore` turns back into a stream.
+  ///
+  /// Checkpoints store the byte length of every root, so they're only
+  /// available for streams using the default weight.
+  pub fn checkpoint(&self) -> Vec<u8> {
+    checkpoint::encode(self.blocks, &self.roots)
+  }
+}
+
+impl<H, W> MerkleTreeStream<H, Vec<u8>, W>
+where
+  H: TryHashMethods<Vec<u8>, W>,
+  W: Weight,
+{
+  /// Like `next`, but propagates errors from the hash functions.
+  ///
+  /// If hashing fails the stream is left exactly as it was: `roots`,
+  /// `blocks` and `nodes` are not modified.
+  pub fn try_next(
+    &mut self,
+    data: &[u8],
+    nodes: &mut Vec<Arc<H::Node>>,
+  ) -> Result<(), H::Error> {
+    self.try_next_owned(data.to_vec(), nodes)
+  }
+
+  /// Like `extend`, but propagates errors from the hash functions.
+  ///
+  /// If hashing fails, the blocks before the failing one stay appended and
+  /// the stream is left as it was after the last of them.
+  pub fn try_extend<I>(
+    &mut self,
+    blocks: I,
+    nodes: &mut Vec<Arc<H::Node>>,
+  ) -> Result<(), H::Error>
+  where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+  {
+    let blocks = blocks.into_iter();
+
+    // Every block adds one leaf and, on average, one parent.
+    let (lower, _) = blocks.size_hint();
+    nodes.reserve(lower.saturating_mul(2));
+
+    for block in blocks {
+      self.try_next(block.as_ref(), nodes)?;
+    }
+    Ok(())
+  }
+}
+
+impl<H: HashMethods<B, W>, B, W: Weight> MerkleTreeStream<H, B, W> {
+  /// Pass an owned buffer through the flat-tree hash functions, and write the
+  /// result back out to "nodes".
+  ///
+  /// The buffer is moved into the leaf node as-is, so with a buffer type like
+  /// `bytes::Bytes` the block is never copied.
+  pub fn next_owned(&mut self, data: B, nodes: &mut Vec<Arc<H::Node>>)
+  where
+    B: AsRef<[u8]>,
+  {
+    if let Err(err) = self.try_next_owned(data, nodes) {
+      match err {}
+    }
+  }
+
+  /// Get a single digest of the current roots, folding in their index,
+  /// length and hash.
+  ///
+  /// Returns `None` if the hasher doesn't implement `HashMethods::tree`.
+  pub fn tree_hash(&self) -> Option<H::Hash> {
+    self.handler.tree(&self.roots)
+  }
+}
+
+impl<H, W> MerkleTreeStream<H, Vec<u8>, W>
+where
+  H: HashMethods<Vec<u8>, W>,
+  W: Weight,
+{
+  /// Pass a string buffer through the flat-tree hash functions, and write the
+  /// result back out to "nodes".
+  pub fn next(&mut self, data: &[u8], nodes: &mut Vec<Arc<H::Node>>) {
+    if let Err(err) = self.try_next(data, nodes) {
+      match err {}
+    }
+  }
+
+  /// Pass many blocks through the stream in one call, writing the result out
+  /// to "nodes".
+  ///
+  /// Produces the same nodes, in the same order, as calling `next` for every
+  /// block. `HashMethods::leaf` is given the roots as they were after the
+  /// previous block, so every leaf is still hashed and merged into the roots
+  /// one at a time; the only work saved over calling `next` in a loop is
+  /// growing `nodes`, which is reserved for the whole batch up front.
+  pub fn extend<I>(&mut self, blocks: I, nodes: &mut Vec<Arc<H::Node>>)
+  where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+  {
+    if let Err(err) = self.try_extend(blocks, nodes) {
+      match err {}
+    }
+  }
+}
+
+/// Hash two sibling nodes into their parent node.
+pub(crate) fn parent_node<H: HashMethods>(
+  handler: &H,
+  left: &H::Node,
+  right: &H::Node,
+) -> H::Node {
+  match try_parent_node::<H, Vec<u8>, u64>(handler, left, right) {
+    Ok(node) => node,
+    Err(err) => match err {},
+  }
+}
+
+/// Hash two sibling nodes into their parent node, propagating errors.
+pub(crate) fn try_parent_node<H: TryHashMethods<B, W>, B, W: Weight>(
+  handler: &H,
+  left: &H::Node,
+  right: &H::Node,
+) -> Result<H::Node, H::Error> {
+  let index = flat::parent(left.index());
+  let hash = handler.try_parent(left, right)?;
+  let partial = PartialNode {
+    index,
+    parent: flat::parent(index),
+    length: left.weight().combine(&right.weight()),
+    data: NodeKind::Parent,
+  };
+
+  Ok(H::Node::from(NodeParts {
+    node: partial,
+    hash,
+  }))
+}
+
+/// Hash a block of data into the leaf node at `index`, without any roots.
+pub(crate) fn leaf_node<H: HashMethods>(
+  handler: &H,
+  index: u64,
+  data: &[u8],
+) -> H::Node {
+  let leaf = PartialNode {
+    index,
+    parent: flat::parent(index),
+    length: data.len() as u64,
+    data: NodeKind::Leaf(data.to_vec()),
+  };
+
+  let hash = handler.leaf(&leaf, &[]);
+  H::Node::from(NodeParts { node: leaf, hash })
+}
+
+/// Find the root whose subtree covers the flat-tree `index`.
+pub(crate) fn find_root<N: Node>(
+  roots: &[Arc<N>],
+  index: u64,
+) -> Option<&Arc<N>> {
+  roots.iter().find(|root| {
+    let (left, right) = flat::spans(root.index());
+    left <= index && index <= right
+  })
+}
+
+/// Get the number of blocks covered by a set of roots.
+pub(crate) fn blocks_from_roots<N: Node>(roots: &[Arc<N>]) -> u64 {
+  match roots.last() {
+    Some(root) => 1 + flat::right_span(root.index()) / 2,
+    None => 0,
+  }
+}
+
+/// Check that `roots` are exactly the full roots of a tree.
+pub(crate) fn validate_roots<N: Node>(
+  roots: &[Arc<N>],
+) -> Result<(), RootsError> {
+  let mut expected = 0;
+  let mut previous: Option<u64> = None;
+
+  for root in roots {
+    let index = root.index();
+    if index > flat::MAX_INDEX {
+      return Err(RootsError::IndexTooLarge { index });
+    }
+    let (left, right) = flat::spans(index);
+
+    if left != expected {
+      return Err(RootsError::NotContiguous {
+        index,
+        expected_block: expected / 2,
+      });
+    }
+
+    if let Some(previous) = previous {
+      if flat::depth(index) >= flat::depth(previous) {
+        return Err(RootsError::NotDecreasing { index });
+      }
+    }
+
+    if root.parent() != flat::parent(index) {
+      return Err(RootsError::WrongParent {
+        index,
+        parent: root.parent(),
+      });
+    }
+
+    expected = right + 2;
+    previous = Some(index);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,178 @@
+use super::{blocks_from_roots, flat, subtree, HashMethods, Node, NodeStore};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+
+/// Inclusion proof for an arbitrary set of blocks.
+///
+/// Holds the root of every subtree that contains none of the blocks but lies
+/// under a root that covers one of them, ordered from left to right. Interior
+/// nodes shared by several blocks are recomputed once instead of being sent
+/// with every block, and all blocks are checked in a single pass.
+///
+/// ## Example
+/// ```rust
+/// # use merkle_tree_stream::{DefaultNode, HashMethods, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// # use std::sync::Arc;
+/// # struct XorHashMethods;
+/// # impl HashMethods for XorHashMethods {
+/// #   type Node = DefaultNode;
+/// #   type Hash = Vec<u8>;
+/// #   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+/// #     match leaf.data() {
+/// #       NodeKind::Parent => vec![0],
+/// #       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+/// #     }
+/// #   }
+/// #   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+/// #     vec![Node::hash(a).iter().chain(Node::hash(b).iter()).fold(0, |acc, x| acc ^ x)]
+/// #   }
+/// # }
+/// use merkle_tree_stream::MultiProof;
+///
+/// let mut mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+/// let mut nodes = Vec::new();
+/// mts.extend(&[b"a", b"b", b"c", b"d", b"e"], &mut nodes);
+///
+/// let proof = MultiProof::new(&[3, 0], mts.roots(), &nodes).unwrap();
+/// assert_eq!(proof.blocks(), &[0, 3]);
+/// assert_eq!(proof.indices().collect::<Vec<_>>(), vec![2, 4]);
+/// assert!(proof.verify(&XorHashMethods, &[b"a", b"d"], mts.roots()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiProof<N> {
+  blocks: Vec<u64>,
+  nodes: Vec<Arc<N>>,
+}
+
+impl<N: Node> MultiProof<N> {
+  /// Build a proof for `blocks` out of the tree's `roots` and the `nodes`
+  /// emitted by `MerkleTreeStream::next`. The blocks can be given in any
+  /// order, duplicates are ignored.
+  ///
+  /// Returns `None` if no block is given or a block lies past the end of the
+  /// tree, or if a node needed for the proof can't be found in `nodes`.
+  pub fn new(
+    blocks: &[u64],
+    roots: &[Arc<N>],
+    nodes: &[Arc<N>],
+  ) -> Option<Self> {
+    let found = Self::build(blocks, roots, |index| {
+      let node = nodes.iter().find(|node| node.index() == index);
+      Ok::<_, Infallible>(node.map(Arc::clone))
+    });
+
+    match found {
+      Ok(proof) => proof,
+      Err(err) => match err {},
+    }
+  }
+
+  /// Build a proof for `blocks` out of the tree's `roots` and the nodes kept
+  /// in `store`.
+  ///
+  /// Returns `Ok(None)` if no block is given or a block lies past the end of
+  /// the tree, or if a node needed for the proof isn't in the store.
+  pub fn from_store<S: NodeStore<N>>(
+    blocks: &[u64],
+    roots: &[Arc<N>],
+    store: &S,
+  ) -> Result<Option<Self>, S::Error> {
+    Self::build(blocks, roots, |index| store.get(index))
+  }
+
+  fn build<E>(
+    blocks: &[u64],
+    roots: &[Arc<N>],
+    mut get: impl FnMut(u64) -> Result<Option<Arc<N>>, E>,
+  ) -> Result<Option<Self>, E> {
+    let mut blocks = blocks.to_vec();
+    blocks.sort_unstable();
+    blocks.dedup();
+    match blocks.last() {
+      Some(&last) if last < blocks_from_roots(roots) => {}
+      _ => return Ok(None),
+    }
+
+    let mut proof = Vec::new();
+    {
+      let covers = covers(&blocks);
+      for root in roots.iter().filter(|root| covers(root.index())) {
+        if !subtree::collect(root.index(), &covers, &mut get, &mut proof)? {
+          return Ok(None);
+        }
+      }
+    }
+
+    Ok(Some(Self {
+      blocks,
+      nodes: proof,
+    }))
+  }
+
+  /// Create a proof out of parts received from a peer, such as the blocks
+  /// and the nodes of a proof built with `new`.
+  ///
+  /// The parts aren't checked until `verify` is called.
+  pub fn from_parts(blocks: Vec<u64>, nodes: Vec<Arc<N>>) -> Self {
+    Self { blocks, nodes }
+  }
+
+  /// Get the blocks this proof is for, in ascending order.
+  pub fn blocks(&self) -> &[u64] {
+    &self.blocks
+  }
+
+  /// Get the subtree roots that contain none of the blocks, ordered from left
+  /// to right.
+  pub fn nodes(&self) -> &[Arc<N>] {
+    &self.nodes
+  }
+
+  /// Get the flat-tree indices of the subtree roots that contain none of the
+  /// blocks, ordered from left to right.
+  pub fn indices(&self) -> impl Iterator<Item = u64> + '_ {
+    self.nodes.iter().map(|node| node.index())
+  }
+
+  /// Check that `data` holds the content of every proven block, in the order
+  /// of `blocks`, in the tree described by `roots`.
+  ///
+  /// The blocks are rehashed with `HashMethods::leaf` and an empty `roots`
+  /// slice, so this only works for hashers whose leaf hash doesn't depend on
+  /// the roots at the time of insertion.
+  ///
+  /// The blocks and nodes of the proof aren't trusted: a proof whose blocks
+  /// are empty, out of order, repeated or past the end of the tree returns
+  /// `false`.
+  pub fn verify<H, D>(&self, handler: &H, data: &[D], roots: &[Arc<N>]) -> bool
+  where
+    H: HashMethods<Node = N>,
+    D: AsRef<[u8]>,
+  {
+    let ascending = self.blocks.windows(2).all(|pair| pair[0] < pair[1]);
+    match self.blocks.last() {
+      Some(&last) if ascending && last < blocks_from_roots(roots) => {}
+      _ => return false,
+    }
+
+    let covers = covers(&self.blocks);
+    data.len() == self.blocks.len()
+      && subtree::verify(
+        handler,
+        roots,
+        &covers,
+        data.iter().map(AsRef::as_ref),
+        &self.nodes,
+      )
+  }
+}
+
+/// Check if the subtree at an index contains one of the sorted `blocks`.
+fn covers(blocks: &[u64]) -> impl Fn(u64) -> bool + '_ {
+  move |index| {
+    let (left, right) = flat::spans(index);
+    let next = blocks.partition_point(|&block| block < left / 2);
+    blocks.get(next).is_some_and(|&block| block <= right / 2)
+  }
+}
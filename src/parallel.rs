@@ -0,0 +1,45 @@
+//! Parallel leaf hashing with rayon.
+
+use super::{leaf_node, HashMethods, MerkleTreeStream};
+use rayon::prelude::*;
+use std::sync::Arc;
+
+impl<H> MerkleTreeStream<H>
+where
+  H: HashMethods + Sync,
+  H::Node: Send,
+{
+  /// Like `extend`, but hashes the leaves on the rayon thread pool.
+  ///
+  /// Leaves are hashed in parallel with an empty `roots` slice, and parents
+  /// are combined in order on the calling thread. That's only possible when
+  /// `HashMethods::leaf_uses_roots` returns `false`; for any other hasher
+  /// this falls back to `extend`, so the result is always identical to
+  /// `extend`'s.
+  pub fn par_extend<B>(&mut self, blocks: &[B], nodes: &mut Vec<Arc<H::Node>>)
+  where
+    B: AsRef<[u8]> + Sync,
+  {
+    if self.handler.leaf_uses_roots() {
+      return self.extend(blocks, nodes);
+    }
+
+    let first = 2 * self.blocks;
+    let handler = &self.handler;
+    let leaves: Vec<_> = blocks
+      .par_iter()
+      .enumerate()
+      .map(|(i, block)| {
+        let index = first + 2 * i as u64;
+        leaf_node(handler, index, block.as_ref())
+      })
+      .collect();
+
+    nodes.reserve(2 * leaves.len());
+    for leaf in leaves {
+      if let Err(err) = self.try_push_leaf(Arc::new(leaf), nodes) {
+        match err {}
+      }
+    }
+  }
+}
@@ -1,28 +1,35 @@
-use std::ops::{Deref, DerefMut};
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
 
 /// Custom Option type that encodes the presence or absense of data at this node
+///
+/// The data is held in a buffer of type `B`, which can be any owned type such
+/// as `bytes::Bytes` so blocks don't have to be copied into a `Vec`.
 #[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Clone)]
-pub enum NodeKind {
+pub enum NodeKind<B = Vec<u8>> {
   /// No data, only children
   Parent,
   /// Contains data
-  Leaf(Vec<u8>),
+  Leaf(B),
 }
 /// Intermediate Node representation. Same as Node, but without the `.hash`
 /// field.
+///
+/// The weight `W` rolled up the tree is the byte length by default, see
+/// `Weight`.
 #[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Clone)]
-pub struct PartialNode {
+pub struct PartialNode<B = Vec<u8>, W = u64> {
   /// Reference to this node's parent node.
   pub parent: u64,
   /// Data if it's a leaf node, nothing if it's a parent node.
-  pub(crate) data: NodeKind,
+  pub(crate) data: NodeKind<B>,
   /// Total size of all its child nodes combined.
-  pub(crate) length: u64,
+  pub(crate) length: W,
   /// Offset into the flat-tree data structure.
   pub(crate) index: u64,
 }
 
-impl PartialNode {
+impl<B> PartialNode<B> {
   /// Returns the number of elements in the Node, also referred to as its
   /// 'length'.
   pub fn len(&self) -> u64 {
@@ -33,25 +40,32 @@ impl PartialNode {
   pub fn is_empty(&self) -> bool {
     self.length == 0
   }
+}
+
+impl<B, W> PartialNode<B, W> {
+  /// Get the weight of the node's subtree.
+  pub fn weight(&self) -> &W {
+    &self.length
+  }
 
   /// Get the current index into the stream.
   pub fn index(&self) -> u64 {
     self.index
   }
   /// Get the data from the thingy.
-  pub fn data(&self) -> &NodeKind {
+  pub fn data(&self) -> &NodeKind<B> {
     &self.data
   }
 }
 
-impl Deref for PartialNode {
-  type Target = NodeKind;
+impl<B, W> Deref for PartialNode<B, W> {
+  type Target = NodeKind<B>;
   fn deref(&self) -> &Self::Target {
     &self.data
   }
 }
 
-impl DerefMut for PartialNode {
+impl<B, W> DerefMut for PartialNode<B, W> {
   fn deref_mut(&mut self) -> &mut Self::Target {
     &mut self.data
   }
@@ -0,0 +1,172 @@
+use super::{
+  find_root, flat, leaf_node, parent_node, HashMethods, Node, NodeStore,
+};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+
+/// Inclusion proof for a single block.
+///
+/// Holds the sibling of every node on the path from the block's leaf up to the
+/// root that covers it, ordered from the bottom of the tree up. Combined with
+/// the block's data this is enough to recompute that root.
+///
+/// ## Example
+/// ```rust
+/// # use merkle_tree_stream::{DefaultNode, HashMethods, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// # use std::sync::Arc;
+/// # struct XorHashMethods;
+/// # impl HashMethods for XorHashMethods {
+/// #   type Node = DefaultNode;
+/// #   type Hash = Vec<u8>;
+/// #   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+/// #     match leaf.data() {
+/// #       NodeKind::Parent => vec![0],
+/// #       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+/// #     }
+/// #   }
+/// #   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+/// #     vec![Node::hash(a).iter().chain(Node::hash(b).iter()).fold(0, |acc, x| acc ^ x)]
+/// #   }
+/// # }
+/// use merkle_tree_stream::Proof;
+///
+/// let mut mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+/// let mut nodes = Vec::new();
+/// mts.next(b"hello", &mut nodes);
+/// mts.next(b"hashed", &mut nodes);
+/// mts.next(b"world", &mut nodes);
+///
+/// let proof = Proof::new(1, mts.roots(), &nodes).unwrap();
+/// assert_eq!(proof.indices().collect::<Vec<_>>(), vec![0]);
+/// assert!(proof.verify(&XorHashMethods, b"hashed", mts.roots()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Proof<N> {
+  block: u64,
+  nodes: Vec<Arc<N>>,
+}
+
+impl<N: Node> Proof<N> {
+  /// Build a proof for `block` out of the tree's `roots` and the `nodes`
+  /// emitted by `MerkleTreeStream::next`.
+  ///
+  /// Returns `None` if no root covers the block, or if a node needed for the
+  /// proof can't be found in `nodes`.
+  pub fn new(block: u64, roots: &[Arc<N>], nodes: &[Arc<N>]) -> Option<Self> {
+    let found = Self::build(block, roots, |index| {
+      let node = nodes.iter().find(|node| node.index() == index);
+      Ok::<_, Infallible>(node.map(Arc::clone))
+    });
+
+    match found {
+      Ok(proof) => proof,
+      Err(err) => match err {},
+    }
+  }
+
+  /// Build a proof for `block` out of the tree's `roots` and the nodes kept
+  /// in `store`.
+  ///
+  /// Returns `Ok(None)` if no root covers the block, or if a node needed for
+  /// the proof isn't in the store.
+  pub fn from_store<S: NodeStore<N>>(
+    block: u64,
+    roots: &[Arc<N>],
+    store: &S,
+  ) -> Result<Option<Self>, S::Error> {
+    Self::build(block, roots, |index| store.get(index))
+  }
+
+  fn build<E>(
+    block: u64,
+    roots: &[Arc<N>],
+    mut get: impl FnMut(u64) -> Result<Option<Arc<N>>, E>,
+  ) -> Result<Option<Self>, E> {
+    let mut index = match flat::checked_leaf(block) {
+      Some(index) => index,
+      None => return Ok(None),
+    };
+    let root = match find_root(roots, index) {
+      Some(root) => root.index(),
+      None => return Ok(None),
+    };
+
+    let mut proof = Vec::new();
+    while index != root {
+      match get(flat::sibling(index))? {
+        Some(node) => proof.push(node),
+        None => return Ok(None),
+      }
+      index = flat::parent(index);
+    }
+
+    Ok(Some(Self {
+      block,
+      nodes: proof,
+    }))
+  }
+
+  /// Create a proof out of parts received from a peer, such as the block
+  /// and the sibling nodes of a proof built with `new`.
+  ///
+  /// The parts aren't checked until `verify` is called.
+  pub fn from_parts(block: u64, nodes: Vec<Arc<N>>) -> Self {
+    Self { block, nodes }
+  }
+
+  /// Get the block this proof is for.
+  pub fn block(&self) -> u64 {
+    self.block
+  }
+
+  /// Get the sibling nodes, ordered from the leaf up.
+  pub fn nodes(&self) -> &[Arc<N>] {
+    &self.nodes
+  }
+
+  /// Get the flat-tree indices of the sibling nodes, ordered from the leaf up.
+  pub fn indices(&self) -> impl Iterator<Item = u64> + '_ {
+    self.nodes.iter().map(|node| node.index())
+  }
+
+  /// Check that `data` is the content of the proven block in the tree
+  /// described by `roots`.
+  ///
+  /// The block is rehashed with `HashMethods::leaf` and an empty `roots`
+  /// slice, so this only works for hashers whose leaf hash doesn't depend on
+  /// the roots at the time of insertion.
+  ///
+  /// The block and nodes of the proof aren't trusted: a proof whose nodes
+  /// aren't the siblings of the block's path returns `false`.
+  pub fn verify<H>(&self, handler: &H, data: &[u8], roots: &[Arc<N>]) -> bool
+  where
+    H: HashMethods<Node = N>,
+  {
+    let index = match flat::checked_leaf(self.block) {
+      Some(index) => index,
+      None => return false,
+    };
+    let mut node = leaf_node(handler, index, data);
+
+    for sibling in &self.nodes {
+      if node.index() > flat::MAX_INDEX
+        || sibling.index() != flat::sibling(node.index())
+      {
+        return false;
+      }
+
+      node = if sibling.index() < node.index() {
+        parent_node(handler, sibling, &node)
+      } else {
+        parent_node(handler, &node, sibling)
+      };
+    }
+
+    roots.iter().any(|root| {
+      root.index() == node.index()
+        && root.len() == node.len()
+        && root.hash() == node.hash()
+    })
+  }
+}
@@ -0,0 +1,174 @@
+use super::{blocks_from_roots, flat, subtree, HashMethods, Node, NodeStore};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::ops::Range;
+
+/// Inclusion proof for a contiguous range of blocks.
+///
+/// Holds the root of every subtree that lies outside the range but under a
+/// root that covers part of it, ordered from left to right. Combined with the
+/// data of the blocks in the range this is enough to recompute those roots,
+/// and it is smaller than one `Proof` per block because the nodes inside the
+/// range are never sent.
+///
+/// ## Example
+/// ```rust
+/// # use merkle_tree_stream::{DefaultNode, HashMethods, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// # use std::sync::Arc;
+/// # struct XorHashMethods;
+/// # impl HashMethods for XorHashMethods {
+/// #   type Node = DefaultNode;
+/// #   type Hash = Vec<u8>;
+/// #   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+/// #     match leaf.data() {
+/// #       NodeKind::Parent => vec![0],
+/// #       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+/// #     }
+/// #   }
+/// #   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+/// #     vec![Node::hash(a).iter().chain(Node::hash(b).iter()).fold(0, |acc, x| acc ^ x)]
+/// #   }
+/// # }
+/// use merkle_tree_stream::RangeProof;
+///
+/// let mut mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+/// let mut nodes = Vec::new();
+/// mts.extend(&[b"a", b"b", b"c", b"d"], &mut nodes);
+///
+/// let proof = RangeProof::new(1..3, mts.roots(), &nodes).unwrap();
+/// assert_eq!(proof.indices().collect::<Vec<_>>(), vec![0, 6]);
+/// assert!(proof.verify(&XorHashMethods, &[b"b", b"c"], mts.roots()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeProof<N> {
+  start: u64,
+  end: u64,
+  nodes: Vec<Arc<N>>,
+}
+
+impl<N: Node> RangeProof<N> {
+  /// Build a proof for the blocks in `range` out of the tree's `roots` and the
+  /// `nodes` emitted by `MerkleTreeStream::next`.
+  ///
+  /// Returns `None` if the range is empty or runs past the end of the tree, or
+  /// if a node needed for the proof can't be found in `nodes`.
+  pub fn new(
+    range: Range<u64>,
+    roots: &[Arc<N>],
+    nodes: &[Arc<N>],
+  ) -> Option<Self> {
+    let found = Self::build(range, roots, |index| {
+      let node = nodes.iter().find(|node| node.index() == index);
+      Ok::<_, Infallible>(node.map(Arc::clone))
+    });
+
+    match found {
+      Ok(proof) => proof,
+      Err(err) => match err {},
+    }
+  }
+
+  /// Build a proof for the blocks in `range` out of the tree's `roots` and the
+  /// nodes kept in `store`.
+  ///
+  /// Returns `Ok(None)` if the range is empty or runs past the end of the
+  /// tree, or if a node needed for the proof isn't in the store.
+  pub fn from_store<S: NodeStore<N>>(
+    range: Range<u64>,
+    roots: &[Arc<N>],
+    store: &S,
+  ) -> Result<Option<Self>, S::Error> {
+    Self::build(range, roots, |index| store.get(index))
+  }
+
+  fn build<E>(
+    range: Range<u64>,
+    roots: &[Arc<N>],
+    mut get: impl FnMut(u64) -> Result<Option<Arc<N>>, E>,
+  ) -> Result<Option<Self>, E> {
+    let Range { start, end } = range;
+    if start >= end || end > blocks_from_roots(roots) {
+      return Ok(None);
+    }
+
+    let covers = covers(start, end);
+    let mut proof = Vec::new();
+    for root in roots.iter().filter(|root| covers(root.index())) {
+      if !subtree::collect(root.index(), &covers, &mut get, &mut proof)? {
+        return Ok(None);
+      }
+    }
+
+    Ok(Some(Self {
+      start,
+      end,
+      nodes: proof,
+    }))
+  }
+
+  /// Create a proof out of parts received from a peer, such as the range and
+  /// the nodes of a proof built with `new`.
+  ///
+  /// The parts aren't checked until `verify` is called.
+  pub fn from_parts(range: Range<u64>, nodes: Vec<Arc<N>>) -> Self {
+    Self {
+      start: range.start,
+      end: range.end,
+      nodes,
+    }
+  }
+
+  /// Get the range of blocks this proof is for.
+  pub fn range(&self) -> Range<u64> {
+    self.start..self.end
+  }
+
+  /// Get the subtree roots outside the range, ordered from left to right.
+  pub fn nodes(&self) -> &[Arc<N>] {
+    &self.nodes
+  }
+
+  /// Get the flat-tree indices of the subtree roots outside the range,
+  /// ordered from left to right.
+  pub fn indices(&self) -> impl Iterator<Item = u64> + '_ {
+    self.nodes.iter().map(|node| node.index())
+  }
+
+  /// Check that `data` holds the content of every block in the proven range,
+  /// in order, in the tree described by `roots`.
+  ///
+  /// The blocks are rehashed with `HashMethods::leaf` and an empty `roots`
+  /// slice, so this only works for hashers whose leaf hash doesn't depend on
+  /// the roots at the time of insertion.
+  ///
+  /// The range and nodes of the proof aren't trusted: a proof for an empty
+  /// range or one that runs past the end of the tree returns `false`.
+  pub fn verify<H, D>(&self, handler: &H, data: &[D], roots: &[Arc<N>]) -> bool
+  where
+    H: HashMethods<Node = N>,
+    D: AsRef<[u8]>,
+  {
+    if self.start >= self.end || self.end > blocks_from_roots(roots) {
+      return false;
+    }
+
+    let covers = covers(self.start, self.end);
+    data.len() as u64 == self.end - self.start
+      && subtree::verify(
+        handler,
+        roots,
+        &covers,
+        data.iter().map(AsRef::as_ref),
+        &self.nodes,
+      )
+  }
+}
+
+/// Check if the subtree at an index contains a block in `start..end`.
+fn covers(start: u64, end: u64) -> impl Fn(u64) -> bool {
+  move |index| {
+    let (left, right) = flat::spans(index);
+    left < 2 * end && right >= 2 * start
+  }
+}
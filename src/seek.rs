@@ -0,0 +1,76 @@
+use super::{flat, Node, NodeStore, SeekError};
+use alloc::sync::Arc;
+
+/// Find the block that contains `byte_offset`, returning the block's index
+/// and the offset within the block.
+///
+/// Walks down from the root covering the offset, comparing it against the
+/// lengths of left children looked up in `store`. Returns `Ok(None)` if the
+/// offset lies past the end of the tree. Empty blocks are never returned.
+///
+/// ## Example
+/// ```rust
+/// # use merkle_tree_stream::{DefaultNode, HashMethods, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// # use std::sync::Arc;
+/// # struct XorHashMethods;
+/// # impl HashMethods for XorHashMethods {
+/// #   type Node = DefaultNode;
+/// #   type Hash = Vec<u8>;
+/// #   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+/// #     match leaf.data() {
+/// #       NodeKind::Parent => vec![0],
+/// #       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+/// #     }
+/// #   }
+/// #   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+/// #     vec![Node::hash(a).iter().chain(Node::hash(b).iter()).fold(0, |acc, x| acc ^ x)]
+/// #   }
+/// # }
+/// use merkle_tree_stream::{seek, MemoryStore};
+///
+/// let mut mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+/// let mut nodes = Vec::new();
+/// mts.next(b"hello", &mut nodes);
+/// mts.next(b"hashed", &mut nodes);
+/// mts.next(b"world", &mut nodes);
+///
+/// let mut store = MemoryStore::new();
+/// store.extend(nodes);
+///
+/// assert_eq!(seek(mts.roots(), &store, 7), Ok(Some((1, 2))));
+/// assert_eq!(seek(mts.roots(), &store, 11), Ok(Some((2, 0))));
+/// assert_eq!(seek(mts.roots(), &store, 16), Ok(None));
+/// ```
+pub fn seek<N: Node, S: NodeStore<N>>(
+  roots: &[Arc<N>],
+  store: &S,
+  byte_offset: u64,
+) -> Result<Option<(u64, u64)>, SeekError<S::Error>> {
+  let mut offset = byte_offset;
+
+  for root in roots {
+    if offset >= root.len() {
+      offset -= root.len();
+      continue;
+    }
+
+    let mut index = root.index();
+    while let Some((left, right)) = flat::children(index) {
+      let left_len = match store.get(left).map_err(SeekError::Store)? {
+        Some(node) => node.len(),
+        None => return Err(SeekError::MissingNode { index: left }),
+      };
+
+      if offset < left_len {
+        index = left;
+      } else {
+        offset -= left_len;
+        index = right;
+      }
+    }
+
+    return Ok(Some((index / 2, offset)));
+  }
+
+  Ok(None)
+}
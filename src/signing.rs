@@ -0,0 +1,139 @@
+//! Ed25519 signatures over the state of a tree.
+//!
+//! The writer of a log signs its tree hash after appending, and readers check
+//! the signature against the writer's public key before trusting the roots
+//! they were sent. Signing requires a `HashMethods` implementation that
+//! provides a tree hash through `HashMethods::tree`.
+
+use super::{blocks_from_roots, validate_roots, HashMethods, MerkleTreeStream};
+use ed25519_dalek::{Signer, Verifier};
+use std::sync::Arc;
+
+pub use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+
+/// The length and tree hash of a tree, signed by its writer.
+///
+/// The signature covers the number of blocks as a big-endian `u64` followed
+/// by the tree hash.
+///
+/// ## Example
+/// ```rust
+/// # use merkle_tree_stream::{DefaultNode, HashMethods, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// # use std::sync::Arc;
+/// # struct XorHashMethods;
+/// # impl HashMethods for XorHashMethods {
+/// #   type Node = DefaultNode;
+/// #   type Hash = Vec<u8>;
+/// #   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+/// #     match leaf.data() {
+/// #       NodeKind::Parent => vec![0],
+/// #       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+/// #     }
+/// #   }
+/// #   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+/// #     vec![Node::hash(a).iter().chain(Node::hash(b).iter()).fold(0, |acc, x| acc ^ x)]
+/// #   }
+/// #   fn tree(&self, roots: &[Arc<Self::Node>]) -> Option<Self::Hash> {
+/// #     Some(roots.iter().map(|root| (root.index() as u8, root.hash()[0])).fold(vec![0, 0], |acc, (i, h)| vec![acc[0] ^ i, acc[1] ^ h]))
+/// #   }
+/// # }
+/// use merkle_tree_stream::signing::{SignedTreeState, SigningKey};
+///
+/// let key = SigningKey::from_bytes(&[7; 32]);
+/// let mut mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+/// let mut nodes = Vec::new();
+/// mts.next(b"hello", &mut nodes);
+/// mts.next(b"world", &mut nodes);
+///
+/// let state = SignedTreeState::sign(&mts, &key).unwrap();
+/// assert_eq!(state.length(), 2);
+/// assert!(state.verify(&key.verifying_key()));
+/// assert!(state.verify_roots(&XorHashMethods, mts.roots()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTreeState {
+  length: u64,
+  tree_hash: Vec<u8>,
+  signature: Signature,
+}
+
+impl SignedTreeState {
+  /// Sign the current state of `stream` with `key`.
+  ///
+  /// Returns `None` if the stream's `HashMethods` don't provide a tree hash.
+  pub fn sign<H>(stream: &MerkleTreeStream<H>, key: &SigningKey) -> Option<Self>
+  where
+    H: HashMethods,
+    H::Hash: AsRef<[u8]>,
+  {
+    let tree_hash = stream.tree_hash()?.as_ref().to_vec();
+    let length = stream.blocks();
+    let signature = key.sign(&message(length, &tree_hash));
+
+    Some(Self {
+      length,
+      tree_hash,
+      signature,
+    })
+  }
+
+  /// Create an instance out of its parts, for instance after receiving them
+  /// from the writer. Nothing is checked until `verify` is called.
+  pub fn from_parts(
+    length: u64,
+    tree_hash: Vec<u8>,
+    signature: Signature,
+  ) -> Self {
+    Self {
+      length,
+      tree_hash,
+      signature,
+    }
+  }
+
+  /// Get the number of blocks in the signed tree.
+  pub fn length(&self) -> u64 {
+    self.length
+  }
+
+  /// Get the signed tree hash.
+  pub fn tree_hash(&self) -> &[u8] {
+    &self.tree_hash
+  }
+
+  /// Get the signature.
+  pub fn signature(&self) -> &Signature {
+    &self.signature
+  }
+
+  /// Check that the state was signed by the owner of `key`.
+  pub fn verify(&self, key: &VerifyingKey) -> bool {
+    key
+      .verify(&message(self.length, &self.tree_hash), &self.signature)
+      .is_ok()
+  }
+
+  /// Check that `roots` are the roots of the signed tree: they must be valid
+  /// roots covering `length()` blocks that fold into the signed tree hash.
+  ///
+  /// This doesn't check the signature itself, see `verify`.
+  pub fn verify_roots<H>(&self, handler: &H, roots: &[Arc<H::Node>]) -> bool
+  where
+    H: HashMethods,
+    H::Hash: AsRef<[u8]>,
+  {
+    validate_roots(roots).is_ok()
+      && blocks_from_roots(roots) == self.length
+      && handler
+        .tree(roots)
+        .is_some_and(|hash| hash.as_ref() == &self.tree_hash[..])
+  }
+}
+
+/// Build the message that gets signed.
+fn message(length: u64, tree_hash: &[u8]) -> Vec<u8> {
+  let mut message = Vec::with_capacity(8 + tree_hash.len());
+  message.extend_from_slice(&length.to_be_bytes());
+  message.extend_from_slice(tree_hash);
+  message
+}
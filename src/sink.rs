@@ -0,0 +1,177 @@
+//! Asynchronous adapter for `MerkleTreeStream`.
+
+use super::{HashMethods, MerkleTreeStream};
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+/// A `Sink` of blocks that is also a `Stream` of the nodes they produce.
+///
+/// Every block sent into the sink is passed through
+/// `MerkleTreeStream::next_owned` right away, and the resulting nodes are
+/// buffered until they're read from the stream side. Once `capacity` nodes are waiting the sink stops accepting
+/// blocks, so a slow consumer throttles the producer. Closing the sink ends
+/// the stream after the buffered nodes have been read.
+///
+/// Blocks are converted into the stream's buffer type `B` first. With a
+/// `MerkleTreeStream<H, Bytes>` they flow through without being copied, while
+/// the default `Vec<u8>` buffers may copy them.
+///
+/// To produce and consume from different tasks, split it with
+/// `futures::StreamExt::split`.
+///
+/// ## Example
+/// ```rust
+/// # use merkle_tree_stream::{DefaultNode, HashMethods, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// # use std::sync::Arc;
+/// # struct XorHashMethods;
+/// # impl HashMethods for XorHashMethods {
+/// #   type Node = DefaultNode;
+/// #   type Hash = Vec<u8>;
+/// #   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+/// #     match leaf.data() {
+/// #       NodeKind::Parent => vec![0],
+/// #       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+/// #     }
+/// #   }
+/// #   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+/// #     vec![Node::hash(a).iter().chain(Node::hash(b).iter()).fold(0, |acc, x| acc ^ x)]
+/// #   }
+/// # }
+/// use bytes::Bytes;
+/// use futures::{SinkExt, StreamExt};
+/// use merkle_tree_stream::sink::MerkleSink;
+///
+/// futures::executor::block_on(async {
+///   let mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+///   let mut sink = MerkleSink::new(mts, 16);
+///
+///   sink.send(Bytes::from_static(b"hello")).await.unwrap();
+///   sink.send(Bytes::from_static(b"world")).await.unwrap();
+///   sink.close().await.unwrap();
+///
+///   let nodes: Vec<_> = sink.collect().await;
+///   assert_eq!(nodes.len(), 3);
+/// });
+/// ```
+#[derive(Debug)]
+pub struct MerkleSink<H: HashMethods<B>, B = Vec<u8>> {
+  stream: MerkleTreeStream<H, B>,
+  pending: VecDeque<Arc<H::Node>>,
+  scratch: Vec<Arc<H::Node>>,
+  capacity: usize,
+  closed: bool,
+  sink_waker: Option<Waker>,
+  stream_waker: Option<Waker>,
+}
+
+// Nothing in `MerkleSink` is ever pinned in place.
+impl<H: HashMethods<B>, B> Unpin for MerkleSink<H, B> {}
+
+impl<H: HashMethods<B>, B> MerkleSink<H, B> {
+  /// Create a new instance that buffers at most `capacity` nodes before
+  /// applying backpressure. A `capacity` of zero is treated as one.
+  pub fn new(stream: MerkleTreeStream<H, B>, capacity: usize) -> Self {
+    Self {
+      stream,
+      pending: VecDeque::new(),
+      scratch: Vec::new(),
+      capacity: capacity.max(1),
+      closed: false,
+      sink_waker: None,
+      stream_waker: None,
+    }
+  }
+
+  /// Access the underlying `MerkleTreeStream`.
+  pub fn get_ref(&self) -> &MerkleTreeStream<H, B> {
+    &self.stream
+  }
+
+  /// Get the underlying `MerkleTreeStream`, dropping any nodes that haven't
+  /// been read yet.
+  pub fn into_inner(self) -> MerkleTreeStream<H, B> {
+    self.stream
+  }
+}
+
+impl<H, B> Sink<Bytes> for MerkleSink<H, B>
+where
+  H: HashMethods<B>,
+  B: From<Bytes> + AsRef<[u8]>,
+{
+  type Error = Infallible;
+
+  fn poll_ready(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Result<(), Self::Error>> {
+    let this = self.get_mut();
+    if this.pending.len() >= this.capacity {
+      this.sink_waker = Some(cx.waker().clone());
+      return Poll::Pending;
+    }
+    Poll::Ready(Ok(()))
+  }
+
+  fn start_send(self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
+    let this = self.get_mut();
+    this.stream.next_owned(B::from(item), &mut this.scratch);
+    this.pending.extend(this.scratch.drain(..));
+
+    if let Some(waker) = this.stream_waker.take() {
+      waker.wake();
+    }
+    Ok(())
+  }
+
+  fn poll_flush(
+    self: Pin<&mut Self>,
+    _cx: &mut Context<'_>,
+  ) -> Poll<Result<(), Self::Error>> {
+    // Blocks are hashed as soon as they're sent, there's nothing to flush.
+    Poll::Ready(Ok(()))
+  }
+
+  fn poll_close(
+    self: Pin<&mut Self>,
+    _cx: &mut Context<'_>,
+  ) -> Poll<Result<(), Self::Error>> {
+    let this = self.get_mut();
+    this.closed = true;
+
+    if let Some(waker) = this.stream_waker.take() {
+      waker.wake();
+    }
+    Poll::Ready(Ok(()))
+  }
+}
+
+impl<H: HashMethods<B>, B> Stream for MerkleSink<H, B> {
+  type Item = Arc<H::Node>;
+
+  fn poll_next(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Option<Self::Item>> {
+    let this = self.get_mut();
+    match this.pending.pop_front() {
+      Some(node) => {
+        if let Some(waker) = this.sink_waker.take() {
+          waker.wake();
+        }
+        Poll::Ready(Some(node))
+      }
+      None if this.closed => Poll::Ready(None),
+      None => {
+        this.stream_waker = Some(cx.waker().clone());
+        Poll::Pending
+      }
+    }
+  }
+}
@@ -0,0 +1,178 @@
+use super::{
+  blocks_from_roots, flat, Bitfield, HashMethods, Node, RootsError, Verifier,
+  VerifyError,
+};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A partial copy of a tree, filled in as verified nodes arrive.
+///
+/// Starts out knowing only the trusted roots and length of the tree. Leaves
+/// and subtrees received from peers go through a `Verifier`, and the tree
+/// keeps track of which flat-tree indices have been verified so far.
+///
+/// ## Example
+/// ```rust
+/// # use merkle_tree_stream::{DefaultNode, HashMethods, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// # use std::sync::Arc;
+/// # struct XorHashMethods;
+/// # impl HashMethods for XorHashMethods {
+/// #   type Node = DefaultNode;
+/// #   type Hash = Vec<u8>;
+/// #   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+/// #     match leaf.data() {
+/// #       NodeKind::Parent => vec![0],
+/// #       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+/// #     }
+/// #   }
+/// #   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+/// #     vec![Node::hash(a).iter().chain(Node::hash(b).iter()).fold(0, |acc, x| acc ^ x)]
+/// #   }
+/// # }
+/// use merkle_tree_stream::SparseTree;
+///
+/// let mut mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+/// let mut nodes = Vec::new();
+/// mts.extend(&[b"a", b"b", b"c", b"d"], &mut nodes);
+/// let node = |index| nodes.iter().find(|node| node.index() == index).unwrap();
+///
+/// let roots = mts.roots().to_vec();
+/// let mut tree = SparseTree::new(XorHashMethods, roots, 4).unwrap();
+/// assert_eq!(tree.missing(1), Some(vec![0, 5]));
+///
+/// tree.add_node(Arc::clone(node(5))).unwrap();
+/// assert_eq!(tree.missing(1), Some(vec![0]));
+///
+/// tree.add_node(Arc::clone(node(0))).unwrap();
+/// assert_eq!(tree.missing(1), Some(vec![]));
+/// assert!(!tree.has_block(1));
+///
+/// tree.add_leaf(1, b"b").unwrap();
+/// assert!(tree.has_block(1));
+/// assert!(tree.has(0) && !tree.has_block(0));
+/// ```
+pub struct SparseTree<H: HashMethods> {
+  verifier: Verifier<H>,
+  length: u64,
+}
+
+impl<H: HashMethods> SparseTree<H> {
+  /// Create a new instance from the trusted `roots` of a tree that is
+  /// `length` blocks long.
+  pub fn new(
+    handler: H,
+    roots: Vec<Arc<H::Node>>,
+    length: u64,
+  ) -> Result<Self, RootsError> {
+    let blocks = blocks_from_roots(&roots);
+    if blocks != length {
+      return Err(RootsError::WrongLength {
+        blocks,
+        expected: length,
+      });
+    }
+
+    Ok(Self {
+      verifier: Verifier::new(handler, roots)?,
+      length,
+    })
+  }
+
+  /// Get the number of blocks in the tree.
+  pub fn len(&self) -> u64 {
+    self.length
+  }
+
+  /// Check if the tree has no blocks.
+  pub fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+
+  /// Get the trusted roots.
+  pub fn roots(&self) -> &[Arc<H::Node>] {
+    self.verifier.roots()
+  }
+
+  /// Add the data of `block`. See `Verifier::add_leaf`.
+  pub fn add_leaf(
+    &mut self,
+    block: u64,
+    data: &[u8],
+  ) -> Result<(), VerifyError> {
+    self.verifier.add_leaf(block, data)
+  }
+
+  /// Add a leaf or the root of a subtree. See `Verifier::add_node`.
+  pub fn add_node(&mut self, node: Arc<H::Node>) -> Result<(), VerifyError> {
+    self.verifier.add_node(node)
+  }
+
+  /// Check if the node at `index` has been verified.
+  pub fn has(&self, index: u64) -> bool {
+    self.verifier.is_trusted(index)
+  }
+
+  /// Check if the data of `block` has been verified with `add_leaf`. A leaf
+  /// added with `add_node` only has its hash verified, so it doesn't count.
+  pub fn has_block(&self, block: u64) -> bool {
+    self.verifier.has_block(block)
+  }
+
+  /// Get the verified node at `index`.
+  pub fn get(&self, index: u64) -> Option<&Arc<H::Node>> {
+    self.verifier.get(index)
+  }
+
+  /// Get the verified nodes, ordered by index.
+  pub fn nodes(&self) -> impl Iterator<Item = &Arc<H::Node>> {
+    self.verifier.trusted()
+  }
+
+  /// Get a `Bitfield` of the verified nodes.
+  pub fn bitfield(&self) -> Bitfield {
+    let mut bitfield = Bitfield::new();
+    for node in self.nodes() {
+      bitfield.set(node.index(), true);
+    }
+    bitfield
+  }
+
+  /// Get the flat-tree indices of the nodes still needed to verify `block`,
+  /// ordered from the leaf up.
+  ///
+  /// These are the siblings on the path from the block's leaf up to the
+  /// first verified node, leaving out nodes that have been received and are
+  /// waiting to be verified. The leaf itself is not listed: once these nodes
+  /// are added, the block's data is checked with `add_leaf`. Returns `None`
+  /// if the block lies past the end of the tree.
+  pub fn missing(&self, block: u64) -> Option<Vec<u64>> {
+    if block >= self.length {
+      return None;
+    }
+
+    let mut missing = Vec::new();
+    let mut index = 2 * block;
+    while !self.has(index) {
+      let sibling = flat::sibling(index);
+      if !self.has(sibling) && !self.verifier.is_pending(sibling) {
+        missing.push(sibling);
+      }
+      index = flat::parent(index);
+    }
+    Some(missing)
+  }
+}
+
+impl<H> fmt::Debug for SparseTree<H>
+where
+  H: HashMethods + fmt::Debug,
+  H::Node: fmt::Debug,
+{
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("SparseTree")
+      .field("verifier", &self.verifier)
+      .field("length", &self.length)
+      .finish()
+  }
+}
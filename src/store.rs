@@ -0,0 +1,114 @@
+//! Storage for the nodes of a tree, addressed by flat-tree index.
+
+use super::Node;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+
+/// Storage for the nodes emitted by `MerkleTreeStream`, addressed by their
+/// flat-tree index.
+///
+/// Proofs are built from a store with `Proof::from_store` and
+/// `ConsistencyProof::from_store`.
+pub trait NodeStore<N> {
+  /// The error returned when the underlying storage fails.
+  type Error;
+
+  /// Get the node at `index`, or `None` if it hasn't been stored.
+  fn get(&self, index: u64) -> Result<Option<Arc<N>>, Self::Error>;
+
+  /// Store a node at its own index, replacing whatever was there.
+  fn put(&mut self, node: Arc<N>) -> Result<(), Self::Error>;
+
+  /// Get the number of index slots in the store: one past the highest index
+  /// that has been stored, or zero if the store is empty.
+  fn len(&self) -> u64;
+
+  /// Check if no node has been stored.
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+/// A `NodeStore` that keeps all nodes in memory.
+///
+/// ## Example
+/// ```rust
+/// # use merkle_tree_stream::{DefaultNode, HashMethods, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// # use std::sync::Arc;
+/// # struct XorHashMethods;
+/// # impl HashMethods for XorHashMethods {
+/// #   type Node = DefaultNode;
+/// #   type Hash = Vec<u8>;
+/// #   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+/// #     match leaf.data() {
+/// #       NodeKind::Parent => vec![0],
+/// #       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+/// #     }
+/// #   }
+/// #   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+/// #     vec![Node::hash(a).iter().chain(Node::hash(b).iter()).fold(0, |acc, x| acc ^ x)]
+/// #   }
+/// # }
+/// use merkle_tree_stream::{MemoryStore, NodeStore};
+///
+/// let mut mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+/// let mut nodes = Vec::new();
+/// mts.next(b"hello", &mut nodes);
+/// mts.next(b"world", &mut nodes);
+///
+/// let mut store = MemoryStore::new();
+/// store.extend(nodes);
+///
+/// assert_eq!(store.len(), 3);
+/// assert_eq!(store.get(1).unwrap().unwrap().len(), 10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MemoryStore<N> {
+  nodes: Vec<Option<Arc<N>>>,
+}
+
+impl<N> MemoryStore<N> {
+  /// Create a new, empty instance.
+  pub fn new() -> Self {
+    Self { nodes: Vec::new() }
+  }
+}
+
+impl<N> Default for MemoryStore<N> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<N: Node> NodeStore<N> for MemoryStore<N> {
+  type Error = Infallible;
+
+  fn get(&self, index: u64) -> Result<Option<Arc<N>>, Self::Error> {
+    let node = self.nodes.get(index as usize).and_then(Option::as_ref);
+    Ok(node.map(Arc::clone))
+  }
+
+  fn put(&mut self, node: Arc<N>) -> Result<(), Self::Error> {
+    let index = node.index() as usize;
+    if index >= self.nodes.len() {
+      self.nodes.resize(index + 1, None);
+    }
+    self.nodes[index] = Some(node);
+    Ok(())
+  }
+
+  fn len(&self) -> u64 {
+    self.nodes.len() as u64
+  }
+}
+
+impl<N: Node> Extend<Arc<N>> for MemoryStore<N> {
+  fn extend<I: IntoIterator<Item = Arc<N>>>(&mut self, iter: I) {
+    for node in iter {
+      if let Err(err) = self.put(node) {
+        match err {}
+      }
+    }
+  }
+}
@@ -0,0 +1,89 @@
+//! Descent logic shared by the proofs that cover several blocks.
+//!
+//! A proof for a set of blocks holds every maximal subtree that contains none
+//! of them. `covers(index)` tells whether the subtree at `index` contains a
+//! proven block; both functions walk the covered part of the tree from left
+//! to right, so the proof nodes and the blocks' data are consumed in order.
+
+use super::{flat, leaf_node, parent_node, HashMethods, Node};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::slice;
+
+/// Collect the uncovered subtrees under `index`. Returns `false` if one of
+/// them can't be found.
+pub(crate) fn collect<N: Node, E>(
+  index: u64,
+  covers: &impl Fn(u64) -> bool,
+  get: &mut impl FnMut(u64) -> Result<Option<Arc<N>>, E>,
+  proof: &mut Vec<Arc<N>>,
+) -> Result<bool, E> {
+  if !covers(index) {
+    return Ok(match get(index)? {
+      Some(node) => {
+        proof.push(node);
+        true
+      }
+      None => false,
+    });
+  }
+
+  match flat::children(index) {
+    Some((left, right)) => Ok(
+      collect(left, covers, get, proof)? && collect(right, covers, get, proof)?,
+    ),
+    None => Ok(true),
+  }
+}
+
+/// Recompute the node at `index` out of the proven blocks' data and the
+/// uncovered subtrees.
+pub(crate) fn rebuild<'a, H: HashMethods>(
+  handler: &H,
+  index: u64,
+  covers: &impl Fn(u64) -> bool,
+  data: &mut impl Iterator<Item = &'a [u8]>,
+  proof: &mut slice::Iter<'_, Arc<H::Node>>,
+) -> Option<Arc<H::Node>> {
+  if !covers(index) {
+    let node = proof.next()?;
+    return if node.index() == index {
+      Some(Arc::clone(node))
+    } else {
+      None
+    };
+  }
+
+  match flat::children(index) {
+    Some((left, right)) => {
+      let left = rebuild(handler, left, covers, data, proof)?;
+      let right = rebuild(handler, right, covers, data, proof)?;
+      Some(Arc::new(parent_node(handler, &left, &right)))
+    }
+    None => Some(Arc::new(leaf_node(handler, index, data.next()?))),
+  }
+}
+
+/// Check that every root that contains a proven block can be rebuilt, and
+/// that the data and the proof are used up exactly.
+pub(crate) fn verify<'a, H: HashMethods>(
+  handler: &H,
+  roots: &[Arc<H::Node>],
+  covers: &impl Fn(u64) -> bool,
+  mut data: impl Iterator<Item = &'a [u8]>,
+  nodes: &[Arc<H::Node>],
+) -> bool {
+  let mut proof = nodes.iter();
+  let roots_match =
+    roots
+      .iter()
+      .filter(|root| covers(root.index()))
+      .all(|root| {
+        match rebuild(handler, root.index(), covers, &mut data, &mut proof) {
+          Some(node) => node.len() == root.len() && node.hash() == root.hash(),
+          None => false,
+        }
+      });
+
+  roots_match && data.next().is_none() && proof.next().is_none()
+}
@@ -0,0 +1,296 @@
+use super::{
+  blocks_from_roots, find_root, flat, leaf_node, parent_node, validate_roots,
+  Bitfield, HashMethods, Node, RootsError, VerifyError,
+};
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Checks nodes received from an untrusted peer against a set of trusted
+/// roots.
+///
+/// Leaves and the sibling nodes needed to verify them can be added in any
+/// order. They are held as pending until they hash up to a trusted node
+/// through `HashMethods::parent`, at which point they become trusted
+/// themselves.
+///
+/// Leaves added with `add_leaf` are hashed with an empty `roots` slice, so
+/// they only verify for hashers whose leaf hash doesn't depend on the roots
+/// (see `HashMethods::leaf_uses_roots`). With other hashers, add the leaf
+/// nodes emitted by the writer's stream with `add_node` instead.
+///
+/// A leaf added with `add_node` is only trusted for its hash and length, since
+/// the `Node` trait gives no way to check its data. Only `add_leaf` hashes
+/// block data, and `has_block` tells whether that has happened for a block.
+///
+/// Nodes that never hash up to a trusted node stay pending. When receiving
+/// from untrusted peers, check `pending_len` against a limit that suits the
+/// application, and call `clear_pending` to drop what a peer sent if it
+/// grows past it or a request is abandoned.
+///
+/// ## Example
+/// ```rust
+/// # use merkle_tree_stream::{DefaultNode, HashMethods, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// # use std::sync::Arc;
+/// # struct XorHashMethods;
+/// # impl HashMethods for XorHashMethods {
+/// #   type Node = DefaultNode;
+/// #   type Hash = Vec<u8>;
+/// #   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+/// #     match leaf.data() {
+/// #       NodeKind::Parent => vec![0],
+/// #       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+/// #     }
+/// #   }
+/// #   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+/// #     vec![Node::hash(a).iter().chain(Node::hash(b).iter()).fold(0, |acc, x| acc ^ x)]
+/// #   }
+/// # }
+/// use merkle_tree_stream::Verifier;
+///
+/// let mut mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+/// let mut nodes = Vec::new();
+/// mts.extend(&[b"a", b"b", b"c", b"d"], &mut nodes);
+/// let node = |index| nodes.iter().find(|node| node.index() == index).unwrap();
+///
+/// let roots = mts.roots().to_vec();
+/// let mut verifier = Verifier::new(XorHashMethods, roots).unwrap();
+/// verifier.add_node(Arc::clone(node(5))).unwrap();
+/// verifier.add_leaf(1, b"b").unwrap();
+/// assert!(!verifier.is_trusted(2));
+///
+/// verifier.add_node(Arc::clone(node(0))).unwrap();
+/// assert!(verifier.is_trusted(2));
+/// assert!(verifier.has_block(1));
+/// assert!(!verifier.has_block(0));
+/// ```
+#[derive(Debug)]
+pub struct Verifier<H: HashMethods> {
+  handler: H,
+  roots: Vec<Arc<H::Node>>,
+  trusted: BTreeMap<u64, Arc<H::Node>>,
+  pending: BTreeMap<u64, Arc<H::Node>>,
+  data: Bitfield,
+}
+
+impl<H: HashMethods> Verifier<H> {
+  /// Create a new instance that trusts `roots`, checking that they are the
+  /// full roots of a tree.
+  pub fn new(handler: H, roots: Vec<Arc<H::Node>>) -> Result<Self, RootsError> {
+    validate_roots(&roots)?;
+    let trusted = roots
+      .iter()
+      .map(|root| (root.index(), Arc::clone(root)))
+      .collect();
+
+    Ok(Self {
+      handler,
+      roots,
+      trusted,
+      pending: BTreeMap::new(),
+      data: Bitfield::new(),
+    })
+  }
+
+  /// Add the data of `block`, hashed with `HashMethods::leaf` and an empty
+  /// `roots` slice.
+  ///
+  /// If the leaf was already added with `add_node`, it is replaced by the one
+  /// built from `data` when their hashes match.
+  pub fn add_leaf(
+    &mut self,
+    block: u64,
+    data: &[u8],
+  ) -> Result<(), VerifyError> {
+    let index = match flat::checked_leaf(block) {
+      Some(index) => index,
+      None => {
+        let index = block.saturating_mul(2);
+        return Err(VerifyError::OutOfBounds { index });
+      }
+    };
+    let node = Arc::new(leaf_node(&self.handler, index, data));
+    let known = match self.trusted.get_mut(&index) {
+      Some(trusted) => Some(trusted),
+      None => self.pending.get_mut(&index),
+    };
+    if let Some(known) = known {
+      if !same(&**known, &*node) {
+        return self.add_node(node);
+      }
+      *known = node;
+      self.data.set(index, true);
+      return Ok(());
+    }
+
+    let added = self.add_node(Arc::clone(&node));
+    if self
+      .get_any(index)
+      .is_some_and(|kept| Arc::ptr_eq(kept, &node))
+    {
+      self.data.set(index, true);
+    }
+    added
+  }
+
+  /// Add a node, and trust every pending node that now hashes up to a
+  /// trusted node.
+  ///
+  /// If the pending nodes below a trusted node don't hash up to it, all of
+  /// them are discarded and `VerifyError::Mismatch` names the trusted node.
+  pub fn add_node(&mut self, node: Arc<H::Node>) -> Result<(), VerifyError> {
+    let index = node.index();
+    if find_root(&self.roots, index).is_none() {
+      return Err(VerifyError::OutOfBounds { index });
+    }
+    if let Some(trusted) = self.trusted.get(&index) {
+      return if same(&**trusted, &*node) {
+        Ok(())
+      } else {
+        Err(VerifyError::Mismatch { index })
+      };
+    }
+    if let Some(pending) = self.pending.get(&index) {
+      return if same(&**pending, &*node) {
+        Ok(())
+      } else {
+        Err(VerifyError::Conflict { index })
+      };
+    }
+
+    self.pending.insert(index, node);
+    self.settle(index)
+  }
+
+  /// Hash pending nodes together until they reach trusted nodes, starting
+  /// from `index`.
+  fn settle(&mut self, index: u64) -> Result<(), VerifyError> {
+    let mut queue = vec![index];
+    while let Some(index) = queue.pop() {
+      if let Some(node) = self.trusted.get(&index).map(Arc::clone) {
+        let (left, right) = match flat::children(index) {
+          Some(children) => children,
+          None => continue,
+        };
+        if !self.pending.contains_key(&left)
+          && !self.pending.contains_key(&right)
+        {
+          continue;
+        }
+        let (left_node, right_node) =
+          match (self.get_any(left), self.get_any(right)) {
+            (Some(left), Some(right)) => (left, right),
+            _ => continue,
+          };
+
+        let parent = parent_node(&self.handler, left_node, right_node);
+        if !same(&*node, &parent) {
+          let (start, end) = flat::spans(index);
+          let data = &mut self.data;
+          self.pending.retain(|&index, _| {
+            let keep = index < start || index > end;
+            if !keep {
+              data.set(index, false);
+            }
+            keep
+          });
+          return Err(VerifyError::Mismatch { index });
+        }
+
+        for child in [left, right] {
+          if let Some(node) = self.pending.remove(&child) {
+            self.trusted.insert(child, node);
+            queue.push(child);
+          }
+        }
+      } else {
+        let parent = flat::parent(index);
+        if self.trusted.contains_key(&parent) {
+          queue.push(parent);
+        } else if !self.pending.contains_key(&parent) {
+          let (left, right) = match flat::children(parent) {
+            Some(children) => children,
+            None => continue,
+          };
+          if let (Some(left), Some(right)) =
+            (self.get_any(left), self.get_any(right))
+          {
+            let node = parent_node(&self.handler, left, right);
+            self.pending.insert(parent, Arc::new(node));
+            queue.push(parent);
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+
+  fn get_any(&self, index: u64) -> Option<&Arc<H::Node>> {
+    self
+      .trusted
+      .get(&index)
+      .or_else(|| self.pending.get(&index))
+  }
+
+  /// Get the trusted roots.
+  pub fn roots(&self) -> &[Arc<H::Node>] {
+    &self.roots
+  }
+
+  /// Get the number of blocks covered by the trusted roots.
+  pub fn blocks(&self) -> u64 {
+    blocks_from_roots(&self.roots)
+  }
+
+  /// Get the trusted node at `index`.
+  pub fn get(&self, index: u64) -> Option<&Arc<H::Node>> {
+    self.trusted.get(&index)
+  }
+
+  /// Check if the node at `index` is trusted.
+  pub fn is_trusted(&self, index: u64) -> bool {
+    self.trusted.contains_key(&index)
+  }
+
+  /// Check if the data of `block` has been added with `add_leaf` and its leaf
+  /// is trusted.
+  pub fn has_block(&self, block: u64) -> bool {
+    flat::checked_leaf(block)
+      .is_some_and(|index| self.data.get(index) && self.is_trusted(index))
+  }
+
+  /// Check if the node at `index` has been received but not verified yet.
+  pub fn is_pending(&self, index: u64) -> bool {
+    self.pending.contains_key(&index)
+  }
+
+  /// Get the trusted nodes, ordered by index.
+  pub fn trusted(&self) -> impl Iterator<Item = &Arc<H::Node>> {
+    self.trusted.values()
+  }
+
+  /// Get the nodes that are waiting to be verified, ordered by index.
+  pub fn pending(&self) -> impl Iterator<Item = &Arc<H::Node>> {
+    self.pending.values()
+  }
+
+  /// Get the number of nodes that are waiting to be verified, including the
+  /// parents computed from them.
+  pub fn pending_len(&self) -> usize {
+    self.pending.len()
+  }
+
+  /// Drop every node that is waiting to be verified. Trusted nodes are kept.
+  pub fn clear_pending(&mut self) {
+    for &index in self.pending.keys() {
+      self.data.set(index, false);
+    }
+    self.pending.clear();
+  }
+}
+
+/// Check if two nodes at the same index hold the same length and hash.
+fn same<N: Node>(a: &N, b: &N) -> bool {
+  a.len() == b.len() && a.hash() == b.hash()
+}
@@ -0,0 +1,43 @@
+//! Values rolled up the tree alongside the hashes.
+
+use super::Node;
+
+/// A value that is computed for every leaf and combined into every parent,
+/// such as a byte length, a record count or a range of timestamps.
+///
+/// `combine` must be associative so that a parent's value doesn't depend on
+/// how its subtree was built. `u64` is used by default, and is the byte
+/// length of a node's data.
+pub trait Weight: Clone {
+  /// Compute the value of a leaf from its data.
+  fn leaf(data: &[u8]) -> Self;
+  /// Combine the values of two siblings, `self` being the left one, into the
+  /// value of their parent.
+  fn combine(&self, right: &Self) -> Self;
+}
+
+impl Weight for u64 {
+  fn leaf(data: &[u8]) -> Self {
+    data.len() as u64
+  }
+
+  fn combine(&self, right: &Self) -> Self {
+    // Lengths can come from untrusted proofs, don't let them overflow.
+    self.saturating_add(*right)
+  }
+}
+
+/// Nodes that carry a `Weight`.
+///
+/// Every `Node` carries its byte length as a `u64` weight. Node types used
+/// with another weight implement this for it.
+pub trait Weighted<W> {
+  /// Get the weight of the node's subtree.
+  fn weight(&self) -> W;
+}
+
+impl<N: Node> Weighted<u64> for N {
+  fn weight(&self) -> u64 {
+    self.len()
+  }
+}
@@ -0,0 +1,208 @@
+//! `std::io::Write` adapter for `MerkleTreeStream`.
+
+use super::chunker::{clamp_cut, Chunker, FixedSize};
+use super::{HashMethods, MerkleTreeStream};
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::vec;
+
+/// A writer that splits everything written to it into blocks and passes them
+/// through a `MerkleTreeStream`.
+///
+/// Blocks have a fixed size by default, any other `Chunker` can be used
+/// through `with_chunker`. All bytes are also forwarded to an inner writer, so
+/// data can be stored and hashed in one pass. Blocks are only hashed once the
+/// chunker can tell where they end; call `finish` once the input ends to hash
+/// whatever is still buffered.
+///
+/// The nodes produced, along with a copy of every block, are kept until they
+/// are taken with `take_nodes` or `drain_nodes`. On long streams, take them
+/// regularly to keep memory use bounded.
+///
+/// ## Example
+/// ```rust
+/// # use merkle_tree_stream::{DefaultNode, HashMethods, MerkleTreeStream, Node, PartialNode, NodeKind};
+/// # use std::sync::Arc;
+/// # struct XorHashMethods;
+/// # impl HashMethods for XorHashMethods {
+/// #   type Node = DefaultNode;
+/// #   type Hash = Vec<u8>;
+/// #   fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+/// #     match leaf.data() {
+/// #       NodeKind::Parent => vec![0],
+/// #       NodeKind::Leaf(data) => vec![data.iter().fold(0, |acc, x| acc ^ x)],
+/// #     }
+/// #   }
+/// #   fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+/// #     vec![Node::hash(a).iter().chain(Node::hash(b).iter()).fold(0, |acc, x| acc ^ x)]
+/// #   }
+/// # }
+/// use merkle_tree_stream::writer::MerkleWriter;
+/// use std::io;
+///
+/// let mts = MerkleTreeStream::new(XorHashMethods, Vec::new());
+/// let mut writer = MerkleWriter::new(mts, io::sink(), 4);
+///
+/// io::copy(&mut &b"hello world"[..], &mut writer).unwrap();
+/// writer.finish().unwrap();
+///
+/// assert_eq!(writer.stream().blocks(), 3);
+/// ```
+#[derive(Debug)]
+pub struct MerkleWriter<H: HashMethods, W, C = FixedSize> {
+  stream: MerkleTreeStream<H>,
+  inner: W,
+  chunker: C,
+  buf: Vec<u8>,
+  nodes: Vec<Arc<H::Node>>,
+}
+
+impl<H: HashMethods, W: Write> MerkleWriter<H, W> {
+  /// Create a new instance that hashes blocks of `block_size` bytes and
+  /// forwards all data to `inner`.
+  ///
+  /// ## Panics
+  /// If `block_size` is zero.
+  pub fn new(stream: MerkleTreeStream<H>, inner: W, block_size: usize) -> Self {
+    Self::with_chunker(stream, inner, FixedSize::new(block_size))
+  }
+
+  /// Get the block size.
+  pub fn block_size(&self) -> usize {
+    self.chunker.size()
+  }
+}
+
+impl<H: HashMethods, W: Write, C: Chunker> MerkleWriter<H, W, C> {
+  /// Create a new instance that splits the data into blocks with `chunker`
+  /// and forwards all data to `inner`.
+  pub fn with_chunker(
+    stream: MerkleTreeStream<H>,
+    inner: W,
+    chunker: C,
+  ) -> Self {
+    Self {
+      stream,
+      inner,
+      chunker,
+      buf: Vec::new(),
+      nodes: Vec::new(),
+    }
+  }
+
+  /// Hash the buffered data, if any, and flush the inner writer.
+  ///
+  /// Data written afterwards starts a new block.
+  pub fn finish(&mut self) -> io::Result<()> {
+    self.hash_buffered(true);
+    self.inner.flush()
+  }
+
+  /// Access the chunker.
+  pub fn chunker(&self) -> &C {
+    &self.chunker
+  }
+
+  /// Access the underlying `MerkleTreeStream`.
+  pub fn stream(&self) -> &MerkleTreeStream<H> {
+    &self.stream
+  }
+
+  /// Get the nodes produced so far that haven't been taken yet.
+  pub fn nodes(&self) -> &[Arc<H::Node>] {
+    &self.nodes
+  }
+
+  /// Take the nodes produced so far.
+  pub fn take_nodes(&mut self) -> Vec<Arc<H::Node>> {
+    std::mem::take(&mut self.nodes)
+  }
+
+  /// Remove the nodes produced so far, keeping the allocation to reuse for
+  /// the next ones.
+  pub fn drain_nodes(&mut self) -> vec::Drain<'_, Arc<H::Node>> {
+    self.nodes.drain(..)
+  }
+
+  /// Access the inner writer.
+  pub fn get_ref(&self) -> &W {
+    &self.inner
+  }
+
+  /// Access the inner writer mutably.
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.inner
+  }
+
+  /// Get the `MerkleTreeStream` and the inner writer back. Any buffered data
+  /// is discarded, so call `finish` first.
+  pub fn into_parts(self) -> (MerkleTreeStream<H>, W) {
+    (self.stream, self.inner)
+  }
+
+  /// Hash the blocks at the start of the buffer, or all of it if `finish` is
+  /// set.
+  fn hash_buffered(&mut self, finish: bool) {
+    let consumed = hash_blocks(
+      &mut self.stream,
+      &self.chunker,
+      &self.buf,
+      finish,
+      &mut self.nodes,
+    );
+    self.buf.drain(..consumed);
+  }
+}
+
+impl<H: HashMethods, W: Write, C: Chunker> Write for MerkleWriter<H, W, C> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let len = self.inner.write(buf)?;
+    let data = &buf[..len];
+
+    if self.buf.is_empty() {
+      // Hash straight from the input, only copying what's left over.
+      let consumed = hash_blocks(
+        &mut self.stream,
+        &self.chunker,
+        data,
+        false,
+        &mut self.nodes,
+      );
+      self.buf.extend_from_slice(&data[consumed..]);
+    } else {
+      self.buf.extend_from_slice(data);
+      self.hash_buffered(false);
+    }
+
+    Ok(len)
+  }
+
+  /// Flushes the inner writer. Buffered data is kept until `finish` is
+  /// called, so flushing doesn't change the shape of the tree.
+  fn flush(&mut self) -> io::Result<()> {
+    self.inner.flush()
+  }
+}
+
+/// Pass the blocks at the start of `data` through the stream, returning the
+/// number of bytes consumed. Unless `finish` is set, only blocks the chunker
+/// can be sure about are hashed.
+fn hash_blocks<H: HashMethods, C: Chunker>(
+  stream: &mut MerkleTreeStream<H>,
+  chunker: &C,
+  data: &[u8],
+  finish: bool,
+  nodes: &mut Vec<Arc<H::Node>>,
+) -> usize {
+  let mut start = 0;
+  loop {
+    let rest = &data[start..];
+    if rest.is_empty() || (!finish && rest.len() < chunker.max_size()) {
+      return start;
+    }
+
+    let len = clamp_cut(chunker, rest);
+    stream.next(&rest[..len], nodes);
+    start += len;
+  }
+}
@@ -0,0 +1,159 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{blocks, build_mts, H};
+use merkle_tree_stream::{
+  Bitfield, BitfieldError, MemoryStore, NodeStore, SparseTree,
+};
+use quickcheck::quickcheck;
+use std::collections::BTreeSet;
+
+#[test]
+fn bitfield_set_and_clear() {
+  let mut bitfield = Bitfield::new();
+  assert!(bitfield.is_empty());
+  assert_eq!(bitfield.next_missing(3), 3);
+
+  bitfield.set_range(0..4, true);
+  bitfield.set_range(6..9, true);
+  bitfield.set(4, true);
+  bitfield.set(5, true);
+  assert_eq!(bitfield.runs().len(), 1);
+  assert_eq!(bitfield.len(), 9);
+
+  bitfield.set_range(2..4, false);
+  bitfield.set(8, false);
+  assert_eq!(bitfield.runs(), &[0..2, 4..8]);
+  assert!(bitfield.has_block(0));
+  assert!(!bitfield.has_block(1));
+  assert!(bitfield.has_all(2..4));
+  assert!(!bitfield.has_all(0..2));
+  assert!(bitfield.has_all(1..1));
+  assert_eq!(bitfield.next_missing(0), 1);
+  assert_eq!(bitfield.next_missing(2), 4);
+}
+
+#[test]
+fn bitfield_from_store() {
+  let data = blocks(5);
+  let (mts, nodes) = build_mts(&data);
+  let mut store = MemoryStore::new();
+  store.extend(nodes.iter().filter(|node| node.index != 2).cloned());
+
+  let bitfield = Bitfield::from_store(&store).unwrap();
+  assert_eq!(bitfield.runs(), &[0..2, 3..7, 8..9]);
+  assert_eq!(bitfield.len(), store.len());
+  assert_eq!(bitfield.next_missing(0), 1);
+  assert!(bitfield.has_all(2..5));
+
+  let tree = SparseTree::new(H, mts.roots().to_vec(), 5).unwrap();
+  assert_eq!(tree.bitfield().runs(), &[3..4, 8..9]);
+}
+
+#[test]
+fn bitfield_rejects_bad_bytes() {
+  assert_eq!(Bitfield::from_bytes(&[]), Ok(Bitfield::new()));
+  assert_eq!(Bitfield::from_bytes(&[2]), Err(BitfieldError::Truncated));
+  assert_eq!(
+    Bitfield::from_bytes(&[2, 0x80]),
+    Err(BitfieldError::Truncated)
+  );
+  assert_eq!(Bitfield::from_bytes(&[2, 0]), Err(BitfieldError::EmptyRun));
+  assert_eq!(
+    Bitfield::from_bytes(&[0, 1, 0, 1]),
+    Err(BitfieldError::EmptyRun)
+  );
+
+  let mut max = vec![0xff; 9];
+  max.push(0x01);
+  assert_eq!(
+    Bitfield::from_bytes(&[&[0][..], &max].concat()),
+    Err(BitfieldError::Overflow)
+  );
+  assert_eq!(
+    Bitfield::from_bytes(&[&[1][..], &max].concat()),
+    Err(BitfieldError::Overflow)
+  );
+  max[9] = 0x02;
+  assert_eq!(
+    Bitfield::from_bytes(&[&[0][..], &max].concat()),
+    Err(BitfieldError::Overflow)
+  );
+}
+
+#[test]
+fn bitfield_range_ends() {
+  let last = Bitfield::MAX_INDEX;
+  let last_block = last / 2;
+
+  let mut bitfield = Bitfield::new();
+  bitfield.set(0, true);
+  bitfield.set(last, true);
+  bitfield.set(last + 1, true);
+  bitfield.set(u64::MAX, true);
+  assert_eq!(bitfield.runs(), &[0..1, last..last + 1]);
+  assert!(bitfield.get(last));
+  assert!(!bitfield.get(u64::MAX));
+  assert!(bitfield.has_block(0));
+  assert!(bitfield.has_block(last_block));
+  assert!(!bitfield.has_block(last_block + 1));
+  assert!(!bitfield.has_block(u64::MAX));
+  assert_eq!(bitfield.next_missing(0), 1);
+  assert_eq!(bitfield.next_missing(last_block), last_block + 1);
+  assert_eq!(bitfield.next_missing(u64::MAX), u64::MAX);
+  assert!(!bitfield.has_all(last_block..u64::MAX));
+
+  bitfield.set_range(0..u64::MAX, true);
+  assert_eq!(bitfield.runs().len(), 1);
+  assert_eq!(bitfield.len(), last + 1);
+  assert_eq!(bitfield.next_missing(0), last_block + 1);
+  assert!(bitfield.has_all(0..last_block + 1));
+  assert!(!bitfield.has_all(0..last_block + 2));
+
+  let bytes = bitfield.to_bytes();
+  assert_eq!(Bitfield::from_bytes(&bytes), Ok(bitfield.clone()));
+
+  bitfield.set(u64::MAX, false);
+  bitfield.set(last, false);
+  assert_eq!(bitfield.runs().len(), 1);
+  assert_eq!(bitfield.len(), last);
+  assert_eq!(bitfield.next_missing(0), last_block);
+}
+
+#[test]
+fn bitfields_match_sets() {
+  fn prop(ops: Vec<(u8, u8, bool)>, block: u8, len: u8) -> bool {
+    let mut bitfield = Bitfield::new();
+    let mut set = BTreeSet::new();
+    for (start, len, value) in ops {
+      let range = u64::from(start)..u64::from(start) + u64::from(len % 16);
+      bitfield.set_range(range.clone(), value);
+      for index in range {
+        if value {
+          set.insert(index);
+        } else {
+          set.remove(&index);
+        }
+      }
+    }
+
+    let block = u64::from(block);
+    let blocks = block..block + u64::from(len % 8);
+    let next_missing = (block..).find(|b| !set.contains(&(2 * b))).unwrap();
+    let runs_valid = bitfield
+      .runs()
+      .windows(2)
+      .all(|pair| pair[0].end < pair[1].start);
+
+    runs_valid
+      && (0..300).all(|index| bitfield.get(index) == set.contains(&index))
+      && bitfield.len() == set.iter().next_back().map_or(0, |last| last + 1)
+      && bitfield.next_missing(block) == next_missing
+      && bitfield.has_all(blocks.clone())
+        == blocks.clone().all(|b| set.contains(&(2 * b)))
+      && Bitfield::from_bytes(&bitfield.to_bytes()) == Ok(bitfield)
+  }
+  quickcheck(prop as fn(Vec<(u8, u8, bool)>, u8, u8) -> bool);
+}
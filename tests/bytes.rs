@@ -0,0 +1,34 @@
+#![cfg(feature = "bytes")]
+
+extern crate bytes;
+extern crate merkle_tree_stream;
+
+mod common;
+
+use bytes::Bytes;
+use common::{build_mts, BytesH};
+use merkle_tree_stream::{MerkleTreeStream, Node};
+
+#[test]
+fn next_owned_with_bytes() {
+  let file = Bytes::from_static(b"hello hashed world");
+  let blocks = [file.slice(0..6), file.slice(6..13), file.slice(13..)];
+
+  let mut mts = MerkleTreeStream::new(BytesH, Vec::new());
+  let mut nodes = Vec::new();
+  for block in &blocks {
+    mts.next_owned(block.clone(), &mut nodes);
+  }
+
+  let data: Vec<Vec<u8>> = blocks.iter().map(|block| block.to_vec()).collect();
+  let (reference, reference_nodes) = build_mts(&data);
+  assert_eq!(mts.blocks(), reference.blocks());
+  for (node, expected) in nodes.iter().zip(&reference_nodes) {
+    assert_eq!(node.index(), expected.index());
+    assert_eq!(node.hash(), expected.hash());
+  }
+
+  // The leaves point into the original buffer.
+  let leaf = nodes[0].data.as_ref().unwrap();
+  assert_eq!(leaf.as_ptr(), file.as_ptr());
+}
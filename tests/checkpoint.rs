@@ -0,0 +1,131 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{blocks, build_mts, H};
+use merkle_tree_stream::{
+  CheckpointError, MerkleTreeStream, Node, Proof, RootsError,
+};
+use quickcheck::quickcheck;
+
+#[test]
+fn checkpoint_layout() {
+  let data = blocks(3);
+  let (mts, _) = build_mts(&data);
+  let checkpoint = mts.checkpoint();
+
+  assert_eq!(&checkpoint[..5], b"MTSC\x01");
+  assert_eq!(&checkpoint[5..13], &3u64.to_be_bytes());
+  assert_eq!(&checkpoint[13..17], &2u32.to_be_bytes());
+  assert_eq!(checkpoint.len(), 17 + 2 * (20 + 64));
+}
+
+#[test]
+fn restore_and_continue() {
+  let data = blocks(10);
+  let (full, _) = build_mts(&data);
+  let (half, _) = build_mts(&data[..6]);
+
+  let mut restored = MerkleTreeStream::restore(H, &half.checkpoint()).unwrap();
+  assert_eq!(restored.blocks(), 6);
+
+  let mut nodes = Vec::new();
+  for block in &data[6..] {
+    restored.next(block, &mut nodes);
+  }
+
+  assert_eq!(restored.blocks(), full.blocks());
+  assert_eq!(restored.roots(), full.roots());
+
+  // Nodes produced after restoring still prove against the full tree.
+  let proof = Proof::new(8, restored.roots(), &nodes).unwrap();
+  assert!(proof.verify(&H, &data[8], full.roots()));
+}
+
+#[test]
+fn restore_empty() {
+  let mts = MerkleTreeStream::new(H, Vec::new());
+  let restored = MerkleTreeStream::restore(H, &mts.checkpoint()).unwrap();
+  assert_eq!(restored.blocks(), 0);
+  assert!(restored.roots().is_empty());
+}
+
+#[test]
+fn restore_rejects_malformed_input() {
+  let data = blocks(3);
+  let (mts, _) = build_mts(&data);
+  let checkpoint = mts.checkpoint();
+
+  let restore = |bytes: &[u8]| MerkleTreeStream::restore(H, bytes).err();
+
+  let mut bad = checkpoint.clone();
+  bad[0] = b'X';
+  assert_eq!(restore(&bad), Some(CheckpointError::BadMagic));
+
+  let mut bad = checkpoint.clone();
+  bad[4] = 2;
+  assert_eq!(restore(&bad), Some(CheckpointError::UnsupportedVersion(2)));
+
+  for len in 0..checkpoint.len() {
+    let err = restore(&checkpoint[..len]);
+    assert!(
+      err == Some(CheckpointError::Truncated)
+        || err == Some(CheckpointError::BadMagic),
+      "length {}: {:?}",
+      len,
+      err
+    );
+  }
+
+  let mut bad = checkpoint.clone();
+  bad.push(0);
+  assert_eq!(restore(&bad), Some(CheckpointError::TrailingBytes));
+
+  let mut bad = checkpoint.clone();
+  bad[12] = 4;
+  assert_eq!(
+    restore(&bad),
+    Some(CheckpointError::BlocksMismatch {
+      blocks: 4,
+      expected: 3
+    })
+  );
+
+  // Give the first root an index no tree can reach.
+  let mut bad = checkpoint.clone();
+  bad[17..25].copy_from_slice(&u64::MAX.to_be_bytes());
+  assert_eq!(
+    restore(&bad),
+    Some(CheckpointError::Roots(RootsError::IndexTooLarge {
+      index: u64::MAX
+    }))
+  );
+
+  // Move the second root from index 4 to index 6.
+  let mut bad = checkpoint;
+  let offset = 17 + 20 + 64 + 7;
+  bad[offset] = 6;
+  assert_eq!(
+    restore(&bad),
+    Some(CheckpointError::Roots(RootsError::NotContiguous {
+      index: 6,
+      expected_block: 2
+    }))
+  );
+}
+
+quickcheck! {
+  fn checkpoint_roundtrip(len: u16) -> bool {
+    let data: Vec<Vec<u8>> =
+      (0..len % 300).map(|i| i.to_be_bytes().to_vec()).collect();
+    let (mts, _) = build_mts(&data);
+
+    let restored = MerkleTreeStream::restore(H, &mts.checkpoint()).unwrap();
+    restored.blocks() == mts.blocks()
+      && restored.roots().len() == mts.roots().len()
+      && restored.roots().iter().zip(mts.roots()).all(|(a, b)| {
+        a.index() == b.index() && a.len() == b.len() && a.hash() == b.hash()
+      })
+  }
+}
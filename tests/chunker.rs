@@ -0,0 +1,126 @@
+#![cfg(feature = "std")]
+
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{build_mts, H};
+use merkle_tree_stream::chunker::{Chunker, FastCdc, FixedSize, Rabin};
+use merkle_tree_stream::writer::MerkleWriter;
+use merkle_tree_stream::{MerkleTreeStream, Node};
+use quickcheck::quickcheck;
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+/// Deterministic pseudo random bytes.
+fn random_bytes(len: usize, seed: u64) -> Vec<u8> {
+  let mut state = seed;
+  (0..len)
+    .map(|_| {
+      state ^= state << 13;
+      state ^= state >> 7;
+      state ^= state << 17;
+      (state >> 24) as u8
+    })
+    .collect()
+}
+
+fn leaf_hashes<C: Chunker>(chunker: C, data: &[u8]) -> HashSet<Vec<u8>> {
+  let mts = MerkleTreeStream::new(H, Vec::new());
+  let mut writer = MerkleWriter::with_chunker(mts, io::sink(), chunker);
+  writer.write_all(data).unwrap();
+  writer.finish().unwrap();
+
+  writer
+    .nodes()
+    .iter()
+    .filter(|node| node.data.is_some())
+    .map(|node| node.hash().to_vec())
+    .collect()
+}
+
+fn check_sizes<C: Chunker>(chunker: &C, data: &[u8], min: usize, max: usize) {
+  let chunks: Vec<_> = chunker.chunks(data).collect();
+  assert_eq!(chunks.concat(), data);
+
+  let (last, rest) = chunks.split_last().unwrap();
+  assert!(last.len() <= max);
+  for chunk in rest {
+    assert!(chunk.len() >= min && chunk.len() <= max, "{}", chunk.len());
+  }
+}
+
+fn check_dedup<C: Chunker + Copy>(chunker: C) {
+  let original = random_bytes(256 * 1024, 0x5eed);
+  let mut edited = original.clone();
+  edited.splice(1000..1000, b"inserted some bytes".iter().cloned());
+
+  let original = leaf_hashes(chunker, &original);
+  let edited = leaf_hashes(chunker, &edited);
+  let shared = original.intersection(&edited).count();
+  assert!(shared * 10 >= original.len() * 9, "{}", shared);
+}
+
+#[test]
+fn fixed_size_chunks() {
+  let chunker = FixedSize::new(4);
+  let chunks: Vec<_> = chunker.chunks(b"hello world").collect();
+  assert_eq!(chunks, vec![&b"hell"[..], b"o wo", b"rld"]);
+}
+
+#[test]
+fn fast_cdc_chunk_sizes() {
+  let data = random_bytes(128 * 1024, 1);
+  check_sizes(&FastCdc::new(512, 2048, 8192), &data, 512, 8192);
+  check_sizes(&FastCdc::new(64, 64, 64), &data, 64, 64);
+}
+
+#[test]
+fn rabin_chunk_sizes() {
+  let data = random_bytes(128 * 1024, 2);
+  check_sizes(&Rabin::new(512, 2048, 8192), &data, 512, 8192);
+  check_sizes(&Rabin::new(16, 32, 64), &data, 16, 64);
+}
+
+#[test]
+fn fast_cdc_survives_insertions() {
+  check_dedup(FastCdc::new(512, 2048, 8192));
+}
+
+#[test]
+fn rabin_survives_insertions() {
+  check_dedup(Rabin::new(512, 2048, 8192));
+}
+
+#[test]
+fn fixed_size_does_not_survive_insertions() {
+  let original = random_bytes(64 * 1024, 3);
+  let mut edited = original.clone();
+  edited.insert(0, 0);
+
+  let original = leaf_hashes(FixedSize::new(2048), &original);
+  let edited = leaf_hashes(FixedSize::new(2048), &edited);
+  assert_eq!(original.intersection(&edited).count(), 0);
+}
+
+#[test]
+fn chunked_writer_matches_chunks() {
+  fn prop(writes: Vec<Vec<u8>>) -> bool {
+    let chunker = FastCdc::new(4, 8, 16);
+    let data = writes.concat();
+
+    let mts = MerkleTreeStream::new(H, Vec::new());
+    let mut writer = MerkleWriter::with_chunker(mts, io::sink(), chunker);
+    for bytes in &writes {
+      writer.write_all(bytes).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let blocks: Vec<Vec<u8>> =
+      chunker.chunks(&data).map(|chunk| chunk.to_vec()).collect();
+    let (_, nodes) = build_mts(&blocks);
+    writer.nodes() == &nodes[..]
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>) -> bool);
+}
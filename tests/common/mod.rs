@@ -0,0 +1,89 @@
+use crypto_hash::{hex_digest, Algorithm};
+use merkle_tree_stream::{
+  DefaultNode, HashMethods, MerkleTreeStream, Node, NodeKind, PartialNode,
+};
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct H;
+impl HashMethods for H {
+  type Node = DefaultNode;
+  type Hash = Vec<u8>;
+
+  fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+    leaf_hash(leaf)
+  }
+
+  fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+    parent_hash(a, b)
+  }
+
+  fn leaf_uses_roots(&self) -> bool {
+    false
+  }
+}
+
+/// `H` for leaves backed by `bytes::Bytes`.
+#[cfg(feature = "bytes")]
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct BytesH;
+#[cfg(feature = "bytes")]
+impl HashMethods<bytes::Bytes> for BytesH {
+  type Node = DefaultNode<bytes::Bytes>;
+  type Hash = Vec<u8>;
+
+  fn leaf(
+    &self,
+    leaf: &PartialNode<bytes::Bytes>,
+    _roots: &[Arc<Self::Node>],
+  ) -> Self::Hash {
+    leaf_hash(leaf)
+  }
+
+  fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+    parent_hash(a, b)
+  }
+}
+
+pub fn build_mts(
+  data: &[Vec<u8>],
+) -> (MerkleTreeStream<H>, Vec<Arc<DefaultNode>>) {
+  let roots = vec![];
+  let mut mts = MerkleTreeStream::new(H, roots);
+  let mut nodes = vec![];
+
+  data.iter().for_each(|bs| mts.next(bs, &mut nodes));
+  (mts, nodes)
+}
+
+/// Get `n` small, distinct blocks of data.
+#[allow(dead_code)]
+pub fn blocks(n: u8) -> Vec<Vec<u8>> {
+  (0..n).map(|i| vec![i; 4]).collect()
+}
+
+/// Find the node at `index` in the nodes emitted by a stream.
+#[allow(dead_code)]
+pub fn node(nodes: &[Arc<DefaultNode>], index: u64) -> Arc<DefaultNode> {
+  let node = nodes.iter().find(|node| node.index() == index).unwrap();
+  Arc::clone(node)
+}
+
+/// The leaf hash of `H`, for hashers with any buffer or weight type.
+pub fn leaf_hash<B: AsRef<[u8]>, W>(leaf: &PartialNode<B, W>) -> Vec<u8> {
+  match leaf.data() {
+    NodeKind::Leaf(data) => hex_digest(Algorithm::SHA256, data.as_ref())
+      .as_bytes()
+      .to_vec(),
+    NodeKind::Parent => vec![],
+  }
+}
+
+/// The parent hash of `H`, for hashers with any node type.
+pub fn parent_hash<N: Node>(a: &N, b: &N) -> Vec<u8> {
+  let mut buf = Vec::with_capacity(a.hash().len() + b.hash().len());
+  buf.extend_from_slice(a.hash());
+  buf.extend_from_slice(b.hash());
+  hex_digest(Algorithm::SHA256, &buf).as_bytes().to_vec()
+}
@@ -0,0 +1,96 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{blocks, build_mts, H};
+use merkle_tree_stream::ConsistencyProof;
+use quickcheck::quickcheck;
+
+#[test]
+fn consistency_proof_indices() {
+  let data = blocks(7);
+  let (old, _) = build_mts(&data[..3]);
+  let (new, nodes) = build_mts(&data);
+
+  // Old roots are 1 and 4, new roots are 3, 9 and 12.
+  let proof = ConsistencyProof::new(3, new.roots(), &nodes).unwrap();
+  assert_eq!(proof.old_blocks(), 3);
+  assert_eq!(proof.new_blocks(), 7);
+  assert_eq!(proof.indices().collect::<Vec<_>>(), vec![6, 9, 12]);
+  assert!(proof.verify(&H, old.roots(), new.roots()));
+
+  assert!(ConsistencyProof::new(8, new.roots(), &nodes).is_none());
+}
+
+#[test]
+fn consistency_proof_detects_forks() {
+  let data = blocks(7);
+  let mut forked = data.clone();
+  forked[1] = b"fork".to_vec();
+
+  let (old, _) = build_mts(&forked[..3]);
+  let (new, nodes) = build_mts(&data);
+
+  let proof = ConsistencyProof::new(3, new.roots(), &nodes).unwrap();
+  assert!(!proof.verify(&H, old.roots(), new.roots()));
+}
+
+#[test]
+fn consistency_proof_from_parts() {
+  let data = blocks(7);
+  let (old, _) = build_mts(&data[..3]);
+  let (new, nodes) = build_mts(&data);
+  let proof = ConsistencyProof::new(3, new.roots(), &nodes).unwrap();
+
+  let nodes = proof.nodes().to_vec();
+  let received = ConsistencyProof::from_parts(3, 7, nodes.clone());
+  assert_eq!(received, proof);
+  assert!(received.verify(&H, old.roots(), new.roots()));
+
+  // A peer can send any lengths and nodes, none of them may panic.
+  let lengths = [(3, 8), (7, 3), (3, u64::MAX), (u64::MAX, u64::MAX)];
+  for (old_blocks, new_blocks) in &lengths {
+    let bad =
+      ConsistencyProof::from_parts(*old_blocks, *new_blocks, nodes.clone());
+    assert!(!bad.verify(&H, old.roots(), new.roots()));
+  }
+
+  let short = ConsistencyProof::from_parts(3, 7, nodes[..2].to_vec());
+  assert!(!short.verify(&H, old.roots(), new.roots()));
+}
+
+#[test]
+fn consistency_proofs_verify() {
+  fn prop(data: Vec<Vec<u8>>, old_blocks: usize) -> bool {
+    let old_blocks = old_blocks % (data.len() + 1);
+    let (old, _) = build_mts(&data[..old_blocks]);
+    let (new, nodes) = build_mts(&data);
+
+    let proof =
+      ConsistencyProof::new(old_blocks as u64, new.roots(), &nodes).unwrap();
+    proof.verify(&H, old.roots(), new.roots())
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>, usize) -> bool);
+}
+
+#[test]
+fn consistency_proofs_fail_for_other_logs() {
+  fn prop(data: Vec<Vec<u8>>, old_blocks: usize, other: Vec<u8>) -> bool {
+    if data.is_empty() {
+      return true;
+    }
+    let old_blocks = 1 + old_blocks % data.len();
+    let mut old_data = data[..old_blocks].to_vec();
+    let changed = old_data[0] != other;
+    old_data[0] = other;
+
+    let (old, _) = build_mts(&old_data);
+    let (new, nodes) = build_mts(&data);
+
+    let proof =
+      ConsistencyProof::new(old_blocks as u64, new.roots(), &nodes).unwrap();
+    proof.verify(&H, old.roots(), new.roots()) != changed
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>, usize, Vec<u8>) -> bool);
+}
@@ -0,0 +1,125 @@
+#![cfg(feature = "std")]
+
+extern crate merkle_tree_stream;
+
+mod common;
+
+use common::{blocks, build_mts, H};
+use merkle_tree_stream::{
+  ConsistencyProof, DefaultNode, FileStore, Node, NodeStore, Proof,
+};
+use std::fs::{self, File, OpenOptions};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn temp_path(name: &str) -> PathBuf {
+  std::env::temp_dir().join(format!(
+    "merkle-tree-stream-{}-{}",
+    name,
+    std::process::id()
+  ))
+}
+
+fn open(path: &PathBuf, truncate: bool) -> File {
+  OpenOptions::new()
+    .read(true)
+    .write(true)
+    .create(true)
+    .truncate(truncate)
+    .open(path)
+    .unwrap()
+}
+
+#[test]
+fn file_store_roundtrip() {
+  let path = temp_path("roundtrip");
+  let data = blocks(5);
+  let (_, nodes) = build_mts(&data);
+
+  let mut store = FileStore::new(open(&path, true), 64);
+  assert_eq!(store.record_size(), 73);
+  for node in &nodes {
+    store.put(Arc::clone(node)).unwrap();
+  }
+  drop(store);
+
+  let store = FileStore::new(open(&path, false), 64);
+  assert_eq!(store.len(), 9);
+  for node in &nodes {
+    let stored = store.get(node.index()).unwrap().unwrap();
+    assert_eq!(stored.index(), node.index());
+    assert_eq!(stored.parent(), node.parent());
+    assert_eq!(stored.len(), node.len());
+    assert_eq!(stored.hash(), node.hash());
+    assert_eq!(stored.data, None);
+  }
+
+  // Index 7 lies in a hole of the file, 9 is past its end.
+  assert!(store.get(7).unwrap().is_none());
+  assert!(store.get(9).unwrap().is_none());
+
+  fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn file_store_rejects_wrong_hash_size() {
+  let path = temp_path("hash-size");
+  let mut store = FileStore::new(open(&path, true), 32);
+  let node = DefaultNode {
+    index: 0,
+    parent: 1,
+    length: 4,
+    hash: vec![0; 31],
+    data: None,
+  };
+
+  assert!(store.put(Arc::new(node)).is_err());
+  assert!(store.is_empty());
+
+  fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn file_store_rejects_huge_index() {
+  let path = temp_path("huge-index");
+  let mut store = FileStore::new(open(&path, true), 32);
+  let node = DefaultNode {
+    index: u64::MAX,
+    parent: u64::MAX,
+    length: 4,
+    hash: vec![0; 32],
+    data: None,
+  };
+
+  let err = store.put(Arc::new(node)).unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::InvalidInput);
+  assert!(store.is_empty());
+  assert!(store.get(u64::MAX).unwrap().is_none());
+
+  fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn proofs_from_file_store() {
+  let path = temp_path("proofs");
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+
+  let mut store = FileStore::new(open(&path, true), 64);
+  for node in &nodes {
+    store.put(Arc::clone(node)).unwrap();
+  }
+
+  let proof = Proof::from_store(2, mts.roots(), &store).unwrap().unwrap();
+  assert_eq!(proof.indices().collect::<Vec<_>>(), vec![6, 1]);
+  assert!(proof.verify(&H, &data[2], mts.roots()));
+
+  let (old, _) = build_mts(&data[..3]);
+  let proof = ConsistencyProof::from_store(3, mts.roots(), &store)
+    .unwrap()
+    .unwrap();
+  assert!(proof.verify(&H, old.roots(), mts.roots()));
+
+  fs::remove_file(&path).unwrap();
+}
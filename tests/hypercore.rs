@@ -0,0 +1,97 @@
+#![cfg(feature = "hypercore")]
+
+extern crate hex;
+extern crate merkle_tree_stream;
+
+use merkle_tree_stream::hypercore::{
+  parent_hash, tree_hash, Blake2bHashMethods,
+};
+use merkle_tree_stream::{
+  DefaultNode, MerkleTreeStream, Node, Proof, Verifier, VerifyError,
+};
+use std::sync::Arc;
+
+// Test vectors taken from the `hypercore-crypto` (2.x) JavaScript package.
+
+#[test]
+fn hash_leaf() {
+  let mut mts = MerkleTreeStream::new(Blake2bHashMethods, Vec::new());
+  let mut nodes = Vec::new();
+  mts.next(b"hello world", &mut nodes);
+
+  let expected =
+    "ccfa4259ee7c41e411e5770973a49c5ceffb5272d6a37f2c6f2dac2190f7e2b7";
+  assert_eq!(hex::encode(nodes[0].hash()), expected);
+}
+
+#[test]
+fn hash_parent() {
+  let mut mts = MerkleTreeStream::new(Blake2bHashMethods, Vec::new());
+  let mut nodes = Vec::new();
+  mts.next(b"hello world", &mut nodes);
+  mts.next(b"hello world", &mut nodes);
+
+  assert_eq!(nodes.len(), 3);
+  assert_eq!(nodes[2].index(), 1);
+  let expected =
+    "43563406adba8b34b133fdca32d0a458c5be769615e01df30e6535ccd3c075f0";
+  assert_eq!(hex::encode(nodes[2].hash()), expected);
+}
+
+#[test]
+fn hash_parent_huge_lengths() {
+  let mut mts = MerkleTreeStream::new(Blake2bHashMethods, Vec::new());
+  let mut nodes = Vec::new();
+  mts.next(b"hello world", &mut nodes);
+  mts.next(b"hello world", &mut nodes);
+
+  let mut huge = DefaultNode::clone(&nodes[1]);
+  huge.length = u64::MAX;
+  let mut saturated = DefaultNode::clone(&nodes[0]);
+  saturated.length = 0;
+  assert_eq!(
+    parent_hash(&*nodes[0], &huge),
+    parent_hash(&saturated, &huge)
+  );
+
+  let huge = Arc::new(huge);
+  let proof = Proof::from_parts(0, vec![Arc::clone(&huge)]);
+  assert!(!proof.verify(&Blake2bHashMethods, b"hello world", mts.roots()));
+
+  let roots = mts.roots().to_vec();
+  let mut verifier = Verifier::new(Blake2bHashMethods, roots).unwrap();
+  verifier.add_node(huge).unwrap();
+  assert_eq!(
+    verifier.add_leaf(0, b"hello world"),
+    Err(VerifyError::Mismatch { index: 1 })
+  );
+}
+
+#[test]
+fn hash_tree() {
+  let root = |index, length| -> Arc<DefaultNode> {
+    Arc::new(DefaultNode {
+      parent: flat_tree::parent(index),
+      data: None,
+      hash: vec![0; 32],
+      length,
+      index,
+    })
+  };
+  let roots = vec![root(3, 11), root(9, 2)];
+
+  let expected =
+    "334dd9d8f9a48c7b7e60affa8704a3597f87fe645fe83f1aada3a1216ea91e65";
+  assert_eq!(hex::encode(tree_hash(&roots)), expected);
+}
+
+#[test]
+fn stream_tree_hash() {
+  let mut mts = MerkleTreeStream::new(Blake2bHashMethods, Vec::new());
+  let mut nodes = Vec::new();
+  for _ in 0..3 {
+    mts.next(b"hello world", &mut nodes);
+  }
+
+  assert_eq!(mts.tree_hash(), Some(tree_hash(mts.roots())));
+}
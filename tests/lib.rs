@@ -4,37 +4,19 @@ extern crate hex;
 extern crate merkle_tree_stream;
 extern crate quickcheck;
 
+mod common;
+
+use common::{blocks, build_mts, leaf_hash, parent_hash, H};
 use crypto_hash::{hex_digest, Algorithm};
 use merkle_tree_stream::{
-  DefaultNode, HashMethods, MerkleTreeStream, Node, NodeKind, PartialNode,
+  DefaultNode, HashMethods, HashNode, MerkleTreeStream, Node, PartialNode,
+  RootsError, TryHashMethods,
 };
 use quickcheck::quickcheck;
 use std::collections::HashSet;
 use std::iter;
 use std::sync::Arc;
 
-struct H;
-impl HashMethods for H {
-  type Node = DefaultNode;
-  type Hash = Vec<u8>;
-
-  fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
-    match leaf.data() {
-      NodeKind::Leaf(data) => {
-        hex_digest(Algorithm::SHA256, &data).as_bytes().to_vec()
-      }
-      NodeKind::Parent => vec![],
-    }
-  }
-
-  fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
-    let mut buf = Vec::with_capacity(a.hash().len() + b.hash().len());
-    buf.extend_from_slice(a.hash());
-    buf.extend_from_slice(b.hash());
-    hex_digest(Algorithm::SHA256, &buf).as_bytes().to_vec()
-  }
-}
-
 #[test]
 fn mts_one_node() {
   let roots = Vec::new();
@@ -115,15 +97,6 @@ fn mts_more_nodes() {
   }
 }
 
-fn build_mts(data: &[Vec<u8>]) -> (MerkleTreeStream<H>, Vec<Arc<DefaultNode>>) {
-  let roots = vec![];
-  let mut mts = MerkleTreeStream::new(H, roots);
-  let mut nodes = vec![];
-
-  data.iter().for_each(|bs| mts.next(&bs, &mut nodes));
-  (mts, nodes)
-}
-
 fn all_children(index: u64) -> Box<dyn Iterator<Item = u64>> {
   let self_ = iter::once(index);
   match flat_tree::children(index) {
@@ -297,3 +270,334 @@ fn mts_new_with_nodes() {
 
   assert_eq!(mts.blocks(), 1);
 }
+
+/// Fails to hash leaves or parents that are longer than `max_len` bytes.
+struct Limited {
+  max_len: u64,
+}
+
+impl TryHashMethods for Limited {
+  type Node = DefaultNode;
+  type Hash = Vec<u8>;
+  type Error = u64;
+
+  fn try_leaf(
+    &self,
+    leaf: &PartialNode,
+    roots: &[Arc<Self::Node>],
+  ) -> Result<Self::Hash, Self::Error> {
+    if leaf.len() > self.max_len {
+      return Err(leaf.index());
+    }
+    Ok(H.leaf(leaf, roots))
+  }
+
+  fn try_parent(
+    &self,
+    a: &Self::Node,
+    b: &Self::Node,
+  ) -> Result<Self::Hash, Self::Error> {
+    if a.len() + b.len() > self.max_len {
+      return Err(flat_tree::parent(a.index()));
+    }
+    Ok(H.parent(a, b))
+  }
+}
+
+#[test]
+fn mts_try_next() {
+  let mut mts = MerkleTreeStream::new(Limited { max_len: 4 }, Vec::new());
+  let mut nodes = Vec::new();
+  assert_eq!(mts.try_next(b"ab", &mut nodes), Ok(()));
+  assert_eq!(mts.try_next(b"cd", &mut nodes), Ok(()));
+  assert_eq!(nodes.len(), 3);
+
+  let (reference, reference_nodes) =
+    build_mts(&[b"ab".to_vec(), b"cd".to_vec()]);
+  assert_eq!(mts.roots(), reference.roots());
+  assert_eq!(nodes, reference_nodes);
+}
+
+#[test]
+fn mts_try_next_failing_leaf() {
+  let mut mts = MerkleTreeStream::new(Limited { max_len: 4 }, Vec::new());
+  let mut nodes = Vec::new();
+  mts.try_next(b"ab", &mut nodes).unwrap();
+
+  assert_eq!(mts.try_next(b"too long", &mut nodes), Err(2));
+  assert_eq!(mts.blocks(), 1);
+  assert_eq!(mts.roots().len(), 1);
+  assert_eq!(nodes.len(), 1);
+}
+
+#[test]
+fn mts_try_next_failing_parent() {
+  let mut mts = MerkleTreeStream::new(Limited { max_len: 4 }, Vec::new());
+  let mut nodes = Vec::new();
+  mts.try_next(b"ab", &mut nodes).unwrap();
+  mts.try_next(b"cd", &mut nodes).unwrap();
+  mts.try_next(b"e", &mut nodes).unwrap();
+  let roots = mts.roots().clone();
+
+  // The leaf hashes fine, but merging it with `e` and then with the
+  // existing root goes over the limit.
+  assert_eq!(mts.try_next(b"f", &mut nodes), Err(3));
+  assert_eq!(mts.blocks(), 3);
+  assert_eq!(mts.roots(), &roots);
+  assert_eq!(nodes.len(), 4);
+
+  mts.try_next(b"", &mut nodes).unwrap_err();
+  assert_eq!(mts.blocks(), 3);
+}
+
+fn root(index: u64) -> Arc<DefaultNode> {
+  Arc::new(DefaultNode {
+    parent: flat_tree::parent(index),
+    data: None,
+    hash: vec![],
+    length: 0,
+    index,
+  })
+}
+
+#[test]
+fn mts_from_roots() {
+  let mts = MerkleTreeStream::from_roots(H, vec![root(7), root(17)]).unwrap();
+  assert_eq!(mts.blocks(), 10);
+
+  let mts = MerkleTreeStream::from_roots(H, vec![]).unwrap();
+  assert_eq!(mts.blocks(), 0);
+}
+
+#[test]
+fn mts_from_roots_rejects_invalid_roots() {
+  let err = MerkleTreeStream::from_roots(H, vec![root(17), root(7)]);
+  assert_eq!(
+    err.unwrap_err(),
+    RootsError::NotContiguous {
+      index: 17,
+      expected_block: 0
+    }
+  );
+
+  let err = MerkleTreeStream::from_roots(H, vec![root(7), root(20)]);
+  assert_eq!(
+    err.unwrap_err(),
+    RootsError::NotContiguous {
+      index: 20,
+      expected_block: 8
+    }
+  );
+
+  let err = MerkleTreeStream::from_roots(H, vec![root(0), root(2)]);
+  assert_eq!(err.unwrap_err(), RootsError::NotDecreasing { index: 2 });
+
+  let err = MerkleTreeStream::from_roots(H, vec![root(1), root(5)]);
+  assert_eq!(err.unwrap_err(), RootsError::NotDecreasing { index: 5 });
+
+  let mut wrong_parent = DefaultNode::clone(&root(3));
+  wrong_parent.parent = 3;
+  let err = MerkleTreeStream::from_roots(H, vec![Arc::new(wrong_parent)]);
+  assert_eq!(
+    err.unwrap_err(),
+    RootsError::WrongParent {
+      index: 3,
+      parent: 3
+    }
+  );
+
+  let mut too_large = DefaultNode::clone(&root(3));
+  too_large.index = u64::MAX;
+  let err = MerkleTreeStream::from_roots(H, vec![Arc::new(too_large)]);
+  assert_eq!(
+    err.unwrap_err(),
+    RootsError::IndexTooLarge { index: u64::MAX }
+  );
+}
+
+#[test]
+fn mts_from_roots_accepts_all_streams() {
+  fn prop(data: Vec<Vec<u8>>) -> bool {
+    let (mts, _) = build_mts(&data);
+    let restored =
+      MerkleTreeStream::from_roots(H, mts.roots().clone()).unwrap();
+    restored.blocks() == mts.blocks()
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>) -> bool);
+}
+
+#[derive(Debug)]
+struct Folding;
+impl HashMethods for Folding {
+  type Node = DefaultNode;
+  type Hash = Vec<u8>;
+
+  fn leaf(&self, leaf: &PartialNode, roots: &[Arc<Self::Node>]) -> Self::Hash {
+    H.leaf(leaf, roots)
+  }
+
+  fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+    H.parent(a, b)
+  }
+
+  fn tree(&self, roots: &[Arc<Self::Node>]) -> Option<Self::Hash> {
+    let mut buf = Vec::new();
+    for root in roots {
+      buf.extend_from_slice(root.hash());
+      buf.extend_from_slice(&root.index().to_be_bytes());
+      buf.extend_from_slice(&root.len().to_be_bytes());
+    }
+    Some(hex_digest(Algorithm::SHA256, &buf).into_bytes())
+  }
+}
+
+#[test]
+fn mts_tree_hash() {
+  let (mts, _) = build_mts(&[b"a".to_vec(), b"b".to_vec()]);
+  assert_eq!(mts.tree_hash(), None);
+  assert_eq!(mts.try_tree_hash(), Ok(None));
+
+  let mut mts = MerkleTreeStream::new(Folding, Vec::new());
+  let mut nodes = Vec::new();
+  let empty = mts.tree_hash().unwrap();
+
+  mts.next(b"a", &mut nodes);
+  let one = mts.tree_hash().unwrap();
+  mts.next(b"b", &mut nodes);
+  let two = mts.tree_hash().unwrap();
+
+  assert_ne!(empty, one);
+  assert_ne!(one, two);
+  assert_eq!(Folding.tree(mts.roots()), Some(two.clone()));
+  assert_eq!(mts.try_tree_hash(), Ok(Some(two)));
+}
+
+#[test]
+fn mts_extend() {
+  let data = blocks(5);
+  let (reference, reference_nodes) = build_mts(&data);
+
+  let mut mts = MerkleTreeStream::new(H, Vec::new());
+  let mut nodes = Vec::new();
+  mts.extend(&data[..2], &mut nodes);
+  mts.extend(data[2..].iter().map(Vec::as_slice), &mut nodes);
+
+  assert_eq!(mts.blocks(), 5);
+  assert_eq!(mts.roots(), reference.roots());
+  assert_eq!(nodes, reference_nodes);
+}
+
+#[test]
+fn mts_try_extend_stops_at_failure() {
+  let mut mts = MerkleTreeStream::new(Limited { max_len: 4 }, Vec::new());
+  let mut nodes = Vec::new();
+  let blocks: [&[u8]; 3] = [b"ab", b"too long", b"cd"];
+
+  assert_eq!(mts.try_extend(blocks.iter(), &mut nodes), Err(2));
+  assert_eq!(mts.blocks(), 1);
+  assert_eq!(nodes.len(), 1);
+}
+
+#[test]
+fn mts_extend_matches_next() {
+  fn prop(batches: Vec<Vec<Vec<u8>>>) -> bool {
+    let data: Vec<Vec<u8>> = batches.iter().flatten().cloned().collect();
+    let (reference, reference_nodes) = build_mts(&data);
+
+    let mut mts = MerkleTreeStream::new(H, Vec::new());
+    let mut nodes = Vec::new();
+    for batch in &batches {
+      mts.extend(batch, &mut nodes);
+    }
+
+    mts.blocks() == reference.blocks()
+      && mts.roots() == reference.roots()
+      && nodes == reference_nodes
+  }
+  quickcheck(prop as fn(Vec<Vec<Vec<u8>>>) -> bool);
+}
+
+#[derive(Debug)]
+struct Borrowed;
+impl HashMethods<&'static [u8]> for Borrowed {
+  type Node = DefaultNode<&'static [u8]>;
+  type Hash = Vec<u8>;
+
+  fn leaf(
+    &self,
+    leaf: &PartialNode<&'static [u8]>,
+    _roots: &[Arc<Self::Node>],
+  ) -> Self::Hash {
+    leaf_hash(leaf)
+  }
+
+  fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+    parent_hash(a, b)
+  }
+}
+
+#[test]
+fn mts_next_owned_does_not_copy() {
+  static DATA: [&[u8]; 3] = [b"hello", b"hashed", b"world"];
+  let mut mts = MerkleTreeStream::new(Borrowed, Vec::new());
+  let mut nodes = Vec::new();
+  for block in DATA.iter() {
+    mts.next_owned(block, &mut nodes);
+  }
+
+  let (reference, reference_nodes) =
+    build_mts(&DATA.iter().map(|block| block.to_vec()).collect::<Vec<_>>());
+  assert_eq!(mts.blocks(), reference.blocks());
+  for (node, expected) in nodes.iter().zip(&reference_nodes) {
+    assert_eq!(node.index(), expected.index());
+    assert_eq!(node.hash(), expected.hash());
+  }
+
+  let leaf = nodes[3].data.unwrap();
+  assert_eq!(nodes[3].index(), 4);
+  assert_eq!(leaf.as_ptr(), DATA[2].as_ptr());
+}
+
+#[derive(Debug)]
+struct HashOnly;
+impl HashMethods for HashOnly {
+  type Node = HashNode;
+  type Hash = Vec<u8>;
+
+  fn leaf(&self, leaf: &PartialNode, _roots: &[Arc<Self::Node>]) -> Self::Hash {
+    leaf_hash(leaf)
+  }
+
+  fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+    parent_hash(a, b)
+  }
+}
+
+#[test]
+fn mts_hash_only_nodes() {
+  fn prop(data: Vec<Vec<u8>>) -> bool {
+    let (reference, reference_nodes) = build_mts(&data);
+
+    let mut mts = MerkleTreeStream::new(HashOnly, Vec::new());
+    let mut nodes = Vec::new();
+    mts.extend(&data, &mut nodes);
+
+    let expected: Vec<HashNode> = reference_nodes
+      .iter()
+      .map(|node| HashNode::from(DefaultNode::clone(node)))
+      .collect();
+    let roots: Vec<HashNode> = reference
+      .roots()
+      .iter()
+      .map(|node| HashNode::from(DefaultNode::clone(node)))
+      .collect();
+
+    nodes.iter().map(|node| HashNode::clone(node)).eq(expected)
+      && mts
+        .roots()
+        .iter()
+        .map(|node| HashNode::clone(node))
+        .eq(roots)
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>) -> bool);
+}
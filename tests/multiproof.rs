@@ -0,0 +1,130 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{blocks, build_mts, H};
+use merkle_tree_stream::{DefaultNode, MemoryStore, MultiProof};
+use quickcheck::quickcheck;
+use std::sync::Arc;
+
+#[test]
+fn multiproof_indices() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+
+  // Roots are 3, 9 and 12; blocks 0 and 3 share the root 3 and only need the
+  // leaves next to them, block 6 is a root on its own.
+  let proof = MultiProof::new(&[6, 0, 3, 0], mts.roots(), &nodes).unwrap();
+  assert_eq!(proof.blocks(), &[0, 3, 6]);
+  assert_eq!(proof.indices().collect::<Vec<_>>(), vec![2, 4]);
+  let blocks = [&data[0], &data[3], &data[6]];
+  assert!(proof.verify(&H, &blocks, mts.roots()));
+
+  let mut store = MemoryStore::new();
+  store.extend(nodes.iter().cloned());
+  let stored = MultiProof::from_store(&[0, 3, 6], mts.roots(), &store).unwrap();
+  assert_eq!(stored, Some(proof));
+
+  assert!(MultiProof::new(&[], mts.roots(), &nodes).is_none());
+  assert!(MultiProof::new(&[2, 7], mts.roots(), &nodes).is_none());
+}
+
+#[test]
+fn multiproof_rejects_wrong_data() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+  let proof = MultiProof::new(&[1, 4], mts.roots(), &nodes).unwrap();
+
+  assert!(proof.verify(&H, &[&data[1], &data[4]], mts.roots()));
+  assert!(!proof.verify(&H, &[&data[4], &data[1]], mts.roots()));
+  assert!(!proof.verify(&H, &[&data[1]], mts.roots()));
+  assert!(!proof.verify(&H, &[&data[1], &b"nope".to_vec()], mts.roots()));
+}
+
+#[test]
+fn multiproof_from_parts() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+  let proof = MultiProof::new(&[1, 4], mts.roots(), &nodes).unwrap();
+  let blocks = [&data[1], &data[4]];
+
+  let nodes = proof.nodes().to_vec();
+  let received = MultiProof::from_parts(vec![1, 4], nodes.clone());
+  assert_eq!(received, proof);
+  assert!(received.verify(&H, &blocks, mts.roots()));
+
+  // Blocks must be non-empty, strictly ascending and inside the tree.
+  let bad_blocks = [
+    vec![],
+    vec![4, 1],
+    vec![1, 1, 4],
+    vec![1, 4, 4],
+    vec![1, 7],
+    vec![1, u64::MAX],
+  ];
+  for blocks in &bad_blocks {
+    let bad = MultiProof::from_parts(blocks.clone(), nodes.clone());
+    let data: Vec<&[u8]> = blocks.iter().map(|_| &data[1][..]).collect();
+    assert!(!bad.verify(&H, &data, mts.roots()));
+  }
+  let empty: [&[u8]; 0] = [];
+  assert!(!MultiProof::from_parts(vec![], vec![]).verify(&H, &empty, &[]));
+
+  let reversed = nodes.iter().rev().cloned().collect();
+  let bad = MultiProof::from_parts(vec![1, 4], reversed);
+  assert!(!bad.verify(&H, &blocks, mts.roots()));
+}
+
+#[test]
+fn multiproofs_verify() {
+  fn prop(data: Vec<Vec<u8>>, picks: Vec<usize>) -> bool {
+    if data.is_empty() || picks.is_empty() {
+      return true;
+    }
+    let (mts, nodes) = build_mts(&data);
+    let blocks: Vec<u64> = picks
+      .iter()
+      .map(|pick| (pick % data.len()) as u64)
+      .collect();
+
+    let proof = MultiProof::new(&blocks, mts.roots(), &nodes).unwrap();
+    let blocks: Vec<&Vec<u8>> = proof
+      .blocks()
+      .iter()
+      .map(|&block| &data[block as usize])
+      .collect();
+    proof.verify(&H, &blocks, mts.roots())
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>, Vec<usize>) -> bool);
+}
+
+#[test]
+fn multiproofs_reject_tampered_hashes() {
+  fn prop(data: Vec<Vec<u8>>, picks: Vec<usize>, target: usize) -> bool {
+    if data.is_empty() || picks.is_empty() {
+      return true;
+    }
+    let (mts, mut nodes) = build_mts(&data);
+    let blocks: Vec<u64> = picks
+      .iter()
+      .map(|pick| (pick % data.len()) as u64)
+      .collect();
+
+    let target = target % nodes.len();
+    let mut node = DefaultNode::clone(&nodes[target]);
+    let index = node.index;
+    node.hash[0] ^= 1;
+    nodes[target] = Arc::new(node);
+
+    let proof = MultiProof::new(&blocks, mts.roots(), &nodes).unwrap();
+    let blocks: Vec<&Vec<u8>> = proof
+      .blocks()
+      .iter()
+      .map(|&block| &data[block as usize])
+      .collect();
+    let tampered = proof.indices().any(|i| i == index);
+    proof.verify(&H, &blocks, mts.roots()) != tampered
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>, Vec<usize>, usize) -> bool);
+}
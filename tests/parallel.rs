@@ -0,0 +1,80 @@
+#![cfg(feature = "rayon")]
+
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{build_mts, H};
+use merkle_tree_stream::{
+  DefaultNode, HashMethods, MerkleTreeStream, PartialNode,
+};
+use quickcheck::quickcheck;
+use std::sync::Arc;
+
+/// Mixes the number of roots into every leaf hash.
+struct RootsH;
+impl HashMethods for RootsH {
+  type Node = DefaultNode;
+  type Hash = Vec<u8>;
+
+  fn leaf(&self, leaf: &PartialNode, roots: &[Arc<Self::Node>]) -> Self::Hash {
+    let mut hash = H.leaf(leaf, roots);
+    hash.push(roots.len() as u8);
+    hash
+  }
+
+  fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+    H.parent(a, b)
+  }
+}
+
+#[test]
+fn par_extend_large_batch() {
+  let data: Vec<Vec<u8>> =
+    (0..1000u32).map(|i| i.to_be_bytes().repeat(64)).collect();
+  let (reference, reference_nodes) = build_mts(&data);
+
+  let mut mts = MerkleTreeStream::new(H, Vec::new());
+  let mut nodes = Vec::new();
+  mts.par_extend(&data, &mut nodes);
+
+  assert_eq!(mts.blocks(), 1000);
+  assert_eq!(mts.roots(), reference.roots());
+  assert_eq!(nodes, reference_nodes);
+}
+
+#[test]
+fn par_extend_roots_dependent_leaves() {
+  let data: Vec<Vec<u8>> =
+    (0..100u32).map(|i| i.to_be_bytes().to_vec()).collect();
+
+  let mut reference = MerkleTreeStream::new(RootsH, Vec::new());
+  let mut reference_nodes = Vec::new();
+  reference.extend(&data, &mut reference_nodes);
+
+  let mut mts = MerkleTreeStream::new(RootsH, Vec::new());
+  let mut nodes = Vec::new();
+  mts.par_extend(&data[..37], &mut nodes);
+  mts.par_extend(&data[37..], &mut nodes);
+
+  assert_eq!(mts.roots(), reference.roots());
+  assert_eq!(nodes, reference_nodes);
+}
+
+quickcheck! {
+  fn par_extend_matches_next(batches: Vec<Vec<Vec<u8>>>) -> bool {
+    let data: Vec<Vec<u8>> = batches.iter().flatten().cloned().collect();
+    let (reference, reference_nodes) = build_mts(&data);
+
+    let mut mts = MerkleTreeStream::new(H, Vec::new());
+    let mut nodes = Vec::new();
+    for batch in &batches {
+      mts.par_extend(batch, &mut nodes);
+    }
+
+    mts.blocks() == reference.blocks()
+      && mts.roots() == reference.roots()
+      && nodes == reference_nodes
+  }
+}
@@ -0,0 +1,111 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{blocks, build_mts, H};
+use merkle_tree_stream::{DefaultNode, MemoryStore, Proof};
+use quickcheck::quickcheck;
+use std::sync::Arc;
+
+#[test]
+fn proof_for_block() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+
+  //       3
+  //     /   \
+  //   1       5       9
+  //  / \     / \     / \
+  // 0   2   4   6   8   10   12
+  let proof = Proof::new(2, mts.roots(), &nodes).unwrap();
+  assert_eq!(proof.block(), 2);
+  assert_eq!(proof.indices().collect::<Vec<_>>(), vec![6, 1]);
+  assert!(proof.verify(&H, &data[2], mts.roots()));
+
+  let proof = Proof::new(6, mts.roots(), &nodes).unwrap();
+  assert_eq!(proof.indices().count(), 0);
+  assert!(proof.verify(&H, &data[6], mts.roots()));
+
+  assert!(Proof::new(7, mts.roots(), &nodes).is_none());
+
+  let mut store = MemoryStore::new();
+  store.extend(nodes.iter().cloned());
+  for block in &[1 << 62, 1 << 63, u64::MAX] {
+    assert!(Proof::new(*block, mts.roots(), &nodes).is_none());
+    let stored = Proof::from_store(*block, mts.roots(), &store);
+    assert!(matches!(stored, Ok(None)));
+  }
+}
+
+#[test]
+fn proof_rejects_wrong_data() {
+  let data = blocks(4);
+  let (mts, nodes) = build_mts(&data);
+
+  let proof = Proof::new(1, mts.roots(), &nodes).unwrap();
+  assert!(!proof.verify(&H, &data[0], mts.roots()));
+  assert!(!proof.verify(&H, b"nope", mts.roots()));
+}
+
+#[test]
+fn proof_from_parts() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+  let proof = Proof::new(2, mts.roots(), &nodes).unwrap();
+
+  let received = Proof::from_parts(proof.block(), proof.nodes().to_vec());
+  assert_eq!(received, proof);
+  assert!(received.verify(&H, &data[2], mts.roots()));
+
+  // A peer can send any block and nodes, none of them may panic.
+  for block in &[3, 1 << 62, u64::MAX / 2, u64::MAX] {
+    let bad = Proof::from_parts(*block, proof.nodes().to_vec());
+    assert!(!bad.verify(&H, &data[2], mts.roots()));
+  }
+
+  let reversed = proof.nodes().iter().rev().cloned().collect();
+  assert!(!Proof::from_parts(2, reversed).verify(&H, &data[2], mts.roots()));
+
+  let mut long = DefaultNode::clone(&proof.nodes()[1]);
+  long.length = u64::MAX;
+  let nodes = vec![Arc::clone(&proof.nodes()[0]), Arc::new(long)];
+  assert!(!Proof::from_parts(2, nodes).verify(&H, &data[2], mts.roots()));
+
+  // Siblings of leaf 0 up past the largest index the crate handles.
+  let siblings = (0..64)
+    .map(|depth| {
+      let mut node = DefaultNode::clone(&proof.nodes()[0]);
+      node.index = (3u64 << depth).wrapping_sub(1);
+      Arc::new(node)
+    })
+    .collect();
+  let tall = Proof::from_parts(0, siblings);
+  assert!(!tall.verify(&H, &data[0], mts.roots()));
+}
+
+#[test]
+fn proofs_verify() {
+  fn prop(data: Vec<Vec<u8>>) -> bool {
+    let (mts, nodes) = build_mts(&data);
+    data.iter().enumerate().all(|(block, bytes)| {
+      let proof = Proof::new(block as u64, mts.roots(), &nodes).unwrap();
+      proof.verify(&H, bytes, mts.roots())
+    })
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>) -> bool);
+}
+
+#[test]
+fn proofs_fail_for_other_blocks() {
+  fn prop(data: Vec<Vec<u8>>, block: usize, other: Vec<u8>) -> bool {
+    if data.is_empty() {
+      return true;
+    }
+    let block = block % data.len();
+    let (mts, nodes) = build_mts(&data);
+    let proof = Proof::new(block as u64, mts.roots(), &nodes).unwrap();
+    proof.verify(&H, &other, mts.roots()) == (other == data[block])
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>, usize, Vec<u8>) -> bool);
+}
@@ -0,0 +1,120 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{blocks, build_mts, H};
+use merkle_tree_stream::{MemoryStore, Proof, RangeProof};
+use quickcheck::quickcheck;
+
+#[test]
+fn range_proof_indices() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+
+  // Roots are 3, 9 and 12; blocks 2..5 need the subtree left of them and the
+  // sibling of block 4.
+  let proof = RangeProof::new(2..5, mts.roots(), &nodes).unwrap();
+  assert_eq!(proof.range(), 2..5);
+  assert_eq!(proof.indices().collect::<Vec<_>>(), vec![1, 10]);
+  assert!(proof.verify(&H, &data[2..5], mts.roots()));
+
+  let mut store = MemoryStore::new();
+  store.extend(nodes.iter().cloned());
+  let stored = RangeProof::from_store(2..5, mts.roots(), &store).unwrap();
+  assert_eq!(stored, Some(proof));
+
+  assert!(RangeProof::new(3..3, mts.roots(), &nodes).is_none());
+  assert!(RangeProof::new(5..8, mts.roots(), &nodes).is_none());
+}
+
+#[test]
+fn range_proof_rejects_wrong_data() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+  let proof = RangeProof::new(2..5, mts.roots(), &nodes).unwrap();
+
+  let mut tampered = data[2..5].to_vec();
+  tampered[1] = b"nope".to_vec();
+  assert!(!proof.verify(&H, &tampered, mts.roots()));
+  assert!(!proof.verify(&H, &data[2..4], mts.roots()));
+  assert!(!proof.verify(&H, &data[1..4], mts.roots()));
+}
+
+#[test]
+fn range_proof_from_parts() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+  let proof = RangeProof::new(2..5, mts.roots(), &nodes).unwrap();
+
+  let nodes = proof.nodes().to_vec();
+  let received = RangeProof::from_parts(2..5, nodes.clone());
+  assert_eq!(received, proof);
+  assert!(received.verify(&H, &data[2..5], mts.roots()));
+
+  // A peer can send any range and nodes, none of them may panic.
+  let ranges = [
+    (5, 2),
+    (2, 2),
+    (2, 8),
+    (2, u64::MAX),
+    (u64::MAX - 1, u64::MAX),
+  ];
+  for (start, end) in &ranges {
+    let bad = RangeProof::from_parts(*start..*end, nodes.clone());
+    assert!(!bad.verify(&H, &data[2..5], mts.roots()));
+  }
+
+  let reversed = nodes.iter().rev().cloned().collect();
+  let bad = RangeProof::from_parts(2..5, reversed);
+  assert!(!bad.verify(&H, &data[2..5], mts.roots()));
+}
+
+#[test]
+fn range_proofs_verify() {
+  fn prop(data: Vec<Vec<u8>>, start: usize, len: usize) -> bool {
+    if data.is_empty() {
+      return true;
+    }
+    let start = start % data.len();
+    let end = start + 1 + len % (data.len() - start);
+    let (mts, nodes) = build_mts(&data);
+
+    let range = start as u64..end as u64;
+    let proof = RangeProof::new(range, mts.roots(), &nodes).unwrap();
+    let singles: usize = (start..end)
+      .map(|block| {
+        let proof = Proof::new(block as u64, mts.roots(), &nodes).unwrap();
+        proof.nodes().len()
+      })
+      .sum();
+
+    proof.verify(&H, &data[start..end], mts.roots())
+      && proof.nodes().len() <= singles
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>, usize, usize) -> bool);
+}
+
+#[test]
+fn range_proofs_fail_for_other_data() {
+  fn prop(
+    data: Vec<Vec<u8>>,
+    start: usize,
+    block: usize,
+    other: Vec<u8>,
+  ) -> bool {
+    if data.is_empty() {
+      return true;
+    }
+    let start = start % data.len();
+    let block = start + block % (data.len() - start);
+    let (mts, nodes) = build_mts(&data);
+
+    let range = start as u64..data.len() as u64;
+    let proof = RangeProof::new(range, mts.roots(), &nodes).unwrap();
+    let mut tampered = data[start..].to_vec();
+    tampered[block - start] = other.clone();
+    proof.verify(&H, &tampered, mts.roots()) == (other == data[block])
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>, usize, usize, Vec<u8>) -> bool);
+}
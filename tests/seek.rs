@@ -0,0 +1,69 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{blocks, build_mts};
+use merkle_tree_stream::{seek, MemoryStore, Node, SeekError};
+use quickcheck::quickcheck;
+
+/// Find the block containing `offset` by walking over all blocks.
+fn linear_seek(data: &[Vec<u8>], mut offset: u64) -> Option<(u64, u64)> {
+  for (block, bytes) in data.iter().enumerate() {
+    let len = bytes.len() as u64;
+    if offset < len {
+      return Some((block as u64, offset));
+    }
+    offset -= len;
+  }
+  None
+}
+
+#[test]
+fn seek_skips_empty_blocks() {
+  let data = vec![vec![1; 3], vec![], vec![], vec![2; 2], vec![3; 4]];
+  let (mts, nodes) = build_mts(&data);
+  let mut store = MemoryStore::new();
+  store.extend(nodes);
+
+  assert_eq!(mts.seek(0, &store), Ok(Some((0, 0))));
+  assert_eq!(mts.seek(2, &store), Ok(Some((0, 2))));
+  assert_eq!(mts.seek(3, &store), Ok(Some((3, 0))));
+  assert_eq!(mts.seek(5, &store), Ok(Some((4, 0))));
+  assert_eq!(mts.seek(8, &store), Ok(Some((4, 3))));
+  assert_eq!(mts.seek(9, &store), Ok(None));
+}
+
+#[test]
+fn seek_empty_tree() {
+  let (mts, _) = build_mts(&[]);
+  let store = MemoryStore::new();
+  assert_eq!(mts.seek(0, &store), Ok(None));
+}
+
+#[test]
+fn seek_missing_node() {
+  let data = blocks(4);
+  let (mts, nodes) = build_mts(&data);
+  let mut store = MemoryStore::new();
+  store.extend(nodes.into_iter().filter(|node| node.index() != 4));
+
+  // Only the left children on the path are looked up.
+  assert_eq!(seek(mts.roots(), &store, 3), Ok(Some((0, 3))));
+  assert_eq!(
+    seek(mts.roots(), &store, 13),
+    Err(SeekError::MissingNode { index: 4 })
+  );
+}
+
+quickcheck! {
+  fn seek_matches_linear_scan(data: Vec<Vec<u8>>, offset: u16) -> bool {
+    let (mts, nodes) = build_mts(&data);
+    let mut store = MemoryStore::new();
+    store.extend(nodes);
+
+    let total: u64 = data.iter().map(|block| block.len() as u64).sum();
+    let offset = u64::from(offset) % (total + 2);
+    mts.seek(offset, &store) == Ok(linear_seek(&data, offset))
+  }
+}
@@ -0,0 +1,85 @@
+#![cfg(all(feature = "signing", feature = "hypercore"))]
+
+extern crate merkle_tree_stream;
+
+mod common;
+
+use common::build_mts;
+use merkle_tree_stream::hypercore::Blake2bHashMethods;
+use merkle_tree_stream::signing::{Signature, SignedTreeState, SigningKey};
+use merkle_tree_stream::MerkleTreeStream;
+
+fn build(len: u8) -> MerkleTreeStream<Blake2bHashMethods> {
+  let mut mts = MerkleTreeStream::new(Blake2bHashMethods, Vec::new());
+  let mut nodes = Vec::new();
+  for i in 0..len {
+    mts.next(&[i; 4], &mut nodes);
+  }
+  mts
+}
+
+#[test]
+fn sign_and_verify() {
+  let key = SigningKey::from_bytes(&[1; 32]);
+  let mts = build(5);
+
+  let state = SignedTreeState::sign(&mts, &key).unwrap();
+  assert_eq!(state.length(), 5);
+  assert_eq!(state.tree_hash(), &mts.tree_hash().unwrap()[..]);
+  assert!(state.verify(&key.verifying_key()));
+  assert!(state.verify_roots(&Blake2bHashMethods, mts.roots()));
+}
+
+#[test]
+fn verify_rejects_other_key() {
+  let key = SigningKey::from_bytes(&[1; 32]);
+  let other = SigningKey::from_bytes(&[2; 32]);
+  let state = SignedTreeState::sign(&build(3), &key).unwrap();
+
+  assert!(!state.verify(&other.verifying_key()));
+}
+
+#[test]
+fn verify_rejects_tampered_state() {
+  let key = SigningKey::from_bytes(&[1; 32]);
+  let state = SignedTreeState::sign(&build(3), &key).unwrap();
+  let public = key.verifying_key();
+
+  let longer = SignedTreeState::from_parts(
+    4,
+    state.tree_hash().to_vec(),
+    *state.signature(),
+  );
+  assert!(!longer.verify(&public));
+
+  let mut hash = state.tree_hash().to_vec();
+  hash[0] ^= 1;
+  let forged = SignedTreeState::from_parts(3, hash, *state.signature());
+  assert!(!forged.verify(&public));
+
+  let mut signature = state.signature().to_bytes();
+  signature[0] ^= 1;
+  let forged = SignedTreeState::from_parts(
+    3,
+    state.tree_hash().to_vec(),
+    Signature::from_bytes(&signature),
+  );
+  assert!(!forged.verify(&public));
+}
+
+#[test]
+fn verify_roots_rejects_other_tree() {
+  let key = SigningKey::from_bytes(&[1; 32]);
+  let state = SignedTreeState::sign(&build(3), &key).unwrap();
+
+  assert!(!state.verify_roots(&Blake2bHashMethods, build(4).roots()));
+  assert!(!state.verify_roots(&Blake2bHashMethods, &build(3).roots()[1..]));
+}
+
+#[test]
+fn sign_requires_tree_hash() {
+  let key = SigningKey::from_bytes(&[1; 32]);
+  let (mts, _) = build_mts(&[b"a".to_vec()]);
+
+  assert!(SignedTreeState::sign(&mts, &key).is_none());
+}
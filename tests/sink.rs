@@ -0,0 +1,87 @@
+#![cfg(feature = "futures")]
+
+extern crate bytes;
+extern crate futures;
+extern crate merkle_tree_stream;
+
+mod common;
+
+use bytes::Bytes;
+use common::{build_mts, BytesH, H};
+use futures::executor::block_on;
+use futures::{future, FutureExt, SinkExt, StreamExt};
+use merkle_tree_stream::sink::MerkleSink;
+use merkle_tree_stream::MerkleTreeStream;
+
+#[test]
+fn sink_produces_same_nodes() {
+  let data: Vec<Vec<u8>> = (0..9u8).map(|i| vec![i; 3]).collect();
+  let (mts, expected) = build_mts(&data);
+
+  let sink = MerkleSink::new(MerkleTreeStream::new(H, Vec::new()), 2);
+  let (mut tx, mut rx) = sink.split();
+
+  let producer = async {
+    for block in &data {
+      tx.send(Bytes::copy_from_slice(block)).await.unwrap();
+    }
+    tx.close().await.unwrap();
+  };
+  let consumer = rx.by_ref().collect::<Vec<_>>();
+
+  let ((), nodes) = block_on(future::join(producer, consumer));
+  assert_eq!(nodes, expected);
+
+  let sink = tx.reunite(rx).unwrap();
+  assert_eq!(sink.get_ref().roots(), mts.roots());
+  assert_eq!(sink.into_inner().blocks(), 9);
+}
+
+#[test]
+fn sink_passes_bytes_through() {
+  let file = Bytes::from_static(b"hello hashed world");
+  let mut sink = MerkleSink::new(MerkleTreeStream::new(BytesH, Vec::new()), 8);
+  block_on(sink.send(file.slice(0..6))).unwrap();
+  block_on(sink.send(file.slice(6..))).unwrap();
+  block_on(sink.close()).unwrap();
+
+  let nodes: Vec<_> = block_on(sink.collect());
+  assert_eq!(nodes.len(), 3);
+  let (_, expected) =
+    build_mts(&[b"hello ".to_vec(), b"hashed world".to_vec()]);
+  for (node, expected) in nodes.iter().zip(&expected) {
+    assert_eq!(node.hash, expected.hash);
+  }
+
+  // The leaves point into the original buffer.
+  let leaf = nodes[0].data.as_ref().unwrap();
+  assert_eq!(leaf.as_ptr(), file.as_ptr());
+}
+
+#[test]
+fn sink_applies_backpressure() {
+  let mut sink = MerkleSink::new(MerkleTreeStream::new(H, Vec::new()), 2);
+
+  // Each of these emits a node, the second one also emits a parent.
+  assert!(sink.send(Bytes::from_static(b"a")).now_or_never().is_some());
+  assert!(sink.send(Bytes::from_static(b"b")).now_or_never().is_some());
+
+  // Three nodes are waiting to be read, so the sink is full.
+  assert!(sink.send(Bytes::from_static(b"c")).now_or_never().is_none());
+
+  assert!(sink.next().now_or_never().is_some());
+  assert!(sink.next().now_or_never().is_some());
+  assert!(sink.send(Bytes::from_static(b"c")).now_or_never().is_some());
+  assert_eq!(sink.get_ref().blocks(), 3);
+}
+
+#[test]
+fn stream_ends_when_sink_closes() {
+  let mut sink = MerkleSink::new(MerkleTreeStream::new(H, Vec::new()), 8);
+  assert!(sink.next().now_or_never().is_none());
+
+  block_on(sink.send(Bytes::from_static(b"a"))).unwrap();
+  block_on(sink.close()).unwrap();
+  assert!(block_on(sink.next()).is_some());
+  assert!(block_on(sink.next()).is_none());
+}
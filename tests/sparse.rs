@@ -0,0 +1,107 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{blocks, build_mts, node, H};
+use merkle_tree_stream::{DefaultNode, RootsError, SparseTree, VerifyError};
+use quickcheck::quickcheck;
+use std::sync::Arc;
+
+#[test]
+fn sparse_tree_checks_length() {
+  let data = blocks(7);
+  let (mts, _) = build_mts(&data);
+
+  let err = SparseTree::new(H, mts.roots().to_vec(), 6).unwrap_err();
+  assert_eq!(
+    err,
+    RootsError::WrongLength {
+      blocks: 7,
+      expected: 6
+    }
+  );
+
+  let tree = SparseTree::new(H, mts.roots().to_vec(), 7).unwrap();
+  assert_eq!(tree.len(), 7);
+  assert_eq!(tree.nodes().count(), 3);
+  assert_eq!(tree.missing(7), None);
+}
+
+#[test]
+fn sparse_tree_missing_nodes() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+  let mut tree = SparseTree::new(H, mts.roots().to_vec(), 7).unwrap();
+
+  // Roots are 3, 9 and 12.
+  assert_eq!(tree.missing(2), Some(vec![6, 1]));
+  assert_eq!(tree.missing(5), Some(vec![8]));
+  assert_eq!(tree.missing(6), Some(vec![]));
+
+  tree.add_node(node(&nodes, 1)).unwrap();
+  assert_eq!(tree.missing(2), Some(vec![6]));
+  tree.add_node(node(&nodes, 6)).unwrap();
+  assert!(!tree.has(1));
+  assert_eq!(tree.missing(2), Some(vec![]));
+  assert_eq!(tree.missing(3), Some(vec![4]));
+
+  tree.add_leaf(2, &data[2]).unwrap();
+  assert!(tree.has_block(2));
+  assert!(tree.has(1) && tree.has(5) && tree.has(6));
+  assert_eq!(
+    tree.add_leaf(3, b"nope"),
+    Err(VerifyError::Mismatch { index: 6 })
+  );
+  tree.add_leaf(3, &data[3]).unwrap();
+}
+
+#[test]
+fn sparse_tree_blocks_need_data() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+  let mut tree = SparseTree::new(H, mts.roots().to_vec(), 7).unwrap();
+
+  let mut evil = DefaultNode::clone(&node(&nodes, 0));
+  evil.data = Some(b"EVIL".to_vec());
+  tree.add_node(Arc::new(evil)).unwrap();
+  for index in tree.missing(0).unwrap() {
+    tree.add_node(node(&nodes, index)).unwrap();
+  }
+  assert!(tree.has(0));
+  assert!(!tree.has_block(0));
+  assert!(!tree.has_block(1));
+
+  tree.add_leaf(0, &data[0]).unwrap();
+  assert!(tree.has_block(0));
+  assert!(!tree.has_block(7));
+  assert!(!tree.has_block(u64::MAX / 2 + 1));
+  assert!(!tree.has_block(u64::MAX));
+}
+
+#[test]
+fn sparse_trees_replicate() {
+  fn prop(data: Vec<Vec<u8>>, picks: Vec<usize>) -> bool {
+    if data.is_empty() {
+      return true;
+    }
+    let (mts, nodes) = build_mts(&data);
+    let length = data.len() as u64;
+    let mut tree = SparseTree::new(H, mts.roots().to_vec(), length).unwrap();
+
+    let fetch = |tree: &mut SparseTree<H>, block: u64| {
+      for index in tree.missing(block).unwrap() {
+        tree.add_node(node(&nodes, index)).unwrap();
+      }
+      tree.add_leaf(block, &data[block as usize]).unwrap();
+      tree.has_block(block) && tree.missing(block) == Some(vec![])
+    };
+
+    let picked = picks
+      .iter()
+      .all(|pick| fetch(&mut tree, (pick % data.len()) as u64));
+    let all = (0..length).all(|block| fetch(&mut tree, block));
+    picked && all && tree.nodes().count() == nodes.len()
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>, Vec<usize>) -> bool);
+}
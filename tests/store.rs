@@ -0,0 +1,53 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{blocks, build_mts};
+use merkle_tree_stream::{MemoryStore, Node, NodeStore, Proof};
+use quickcheck::quickcheck;
+
+#[test]
+fn memory_store_get_put() {
+  let data = blocks(5);
+  let (_, nodes) = build_mts(&data);
+
+  let mut store = MemoryStore::new();
+  assert!(store.is_empty());
+  store.extend(nodes.iter().cloned());
+
+  assert_eq!(store.len(), 9);
+  for node in &nodes {
+    assert_eq!(store.get(node.index()).unwrap().as_ref(), Some(node));
+  }
+  assert!(store.get(7).unwrap().is_none());
+  assert!(store.get(100).unwrap().is_none());
+}
+
+#[test]
+fn proof_from_store_with_missing_node() {
+  let data = blocks(4);
+  let (mts, nodes) = build_mts(&data);
+
+  let mut store = MemoryStore::new();
+  store.extend(nodes.into_iter().filter(|node| node.index() != 1));
+
+  assert!(Proof::from_store(3, mts.roots(), &store).unwrap().is_none());
+  assert!(Proof::from_store(1, mts.roots(), &store).unwrap().is_some());
+}
+
+quickcheck! {
+  fn store_proofs_match_slice_proofs(len: u8, block: u8) -> bool {
+    let len = u64::from(len % 64) + 1;
+    let block = u64::from(block) % len;
+    let data: Vec<Vec<u8>> = (0..len).map(|i| i.to_be_bytes().to_vec()).collect();
+    let (mts, nodes) = build_mts(&data);
+
+    let mut store = MemoryStore::new();
+    store.extend(nodes.iter().cloned());
+
+    let from_slice = Proof::new(block, mts.roots(), &nodes);
+    let from_store = Proof::from_store(block, mts.roots(), &store).unwrap();
+    from_slice.is_some() && from_slice == from_store
+  }
+}
@@ -0,0 +1,86 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{blocks, build_mts};
+use merkle_tree_stream::{MemoryStore, TruncateError};
+use quickcheck::quickcheck;
+
+#[test]
+fn truncate_and_append() {
+  let data = blocks(10);
+  let (mut mts, nodes) = build_mts(&data);
+  let mut store = MemoryStore::new();
+  store.extend(nodes);
+
+  mts.truncate(5, &store).unwrap();
+  let (expected, _) = build_mts(&data[..5]);
+  assert_eq!(mts.blocks(), 5);
+  assert_eq!(mts.roots(), expected.roots());
+
+  let mut nodes = Vec::new();
+  mts.next(b"other", &mut nodes);
+  let mut other = data[..5].to_vec();
+  other.push(b"other".to_vec());
+  let (expected, _) = build_mts(&other);
+  assert_eq!(mts.roots(), expected.roots());
+}
+
+#[test]
+fn truncate_to_zero_and_same_length() {
+  let data = blocks(6);
+  let (mut mts, _) = build_mts(&data);
+  let store = MemoryStore::new();
+
+  // The current roots are reused, so nothing is looked up.
+  let roots = mts.roots().clone();
+  mts.truncate(6, &store).unwrap();
+  assert_eq!(mts.roots(), &roots);
+
+  mts.truncate(0, &store).unwrap();
+  assert_eq!(mts.blocks(), 0);
+  assert!(mts.roots().is_empty());
+}
+
+#[test]
+fn truncate_errors() {
+  let data = blocks(4);
+  let (mut mts, nodes) = build_mts(&data);
+  let roots = mts.roots().clone();
+
+  let store = MemoryStore::new();
+  assert_eq!(
+    mts.truncate(5, &store),
+    Err(TruncateError::TooLong {
+      blocks: 4,
+      new_blocks: 5
+    })
+  );
+  assert_eq!(
+    mts.truncate(3, &store),
+    Err(TruncateError::MissingNode { index: 1 })
+  );
+  assert_eq!(mts.blocks(), 4);
+  assert_eq!(mts.roots(), &roots);
+
+  let mut store = MemoryStore::new();
+  store.extend(nodes);
+  assert_eq!(mts.truncate(3, &store), Ok(()));
+}
+
+quickcheck! {
+  fn truncate_matches_rebuild(len: u8, new_len: u8) -> bool {
+    let data = blocks(len);
+    let len = u64::from(len);
+    let new_len = u64::from(new_len) % (len + 1);
+
+    let (mut mts, nodes) = build_mts(&data);
+    let mut store = MemoryStore::new();
+    store.extend(nodes);
+    mts.truncate(new_len, &store).unwrap();
+
+    let (expected, _) = build_mts(&data[..new_len as usize]);
+    mts.blocks() == expected.blocks() && mts.roots() == expected.roots()
+  }
+}
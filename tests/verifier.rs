@@ -0,0 +1,209 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{blocks, build_mts, node, H};
+use merkle_tree_stream::{DefaultNode, MultiProof, Verifier, VerifyError};
+use quickcheck::quickcheck;
+use std::sync::Arc;
+
+#[test]
+fn verifier_trusts_chained_nodes() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+  let mut verifier = Verifier::new(H, mts.roots().to_vec()).unwrap();
+  assert_eq!(verifier.blocks(), 7);
+
+  // Block 2 sits under root 3, next to leaf 6 and subtree 1.
+  verifier.add_node(node(&nodes, 1)).unwrap();
+  verifier.add_leaf(2, &data[2]).unwrap();
+  assert!(!verifier.is_trusted(4));
+  assert_eq!(verifier.pending().count(), 2);
+
+  verifier.add_node(node(&nodes, 6)).unwrap();
+  for index in &[1, 3, 4, 5, 6] {
+    assert!(verifier.is_trusted(*index));
+  }
+  assert_eq!(verifier.get(4), Some(&node(&nodes, 4)));
+  assert_eq!(verifier.pending().count(), 0);
+
+  // Block 6 is a root on its own.
+  verifier.add_leaf(6, &data[6]).unwrap();
+  assert_eq!(verifier.pending().count(), 0);
+}
+
+#[test]
+fn verifier_rejects_bad_nodes() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+  let mut verifier = Verifier::new(H, mts.roots().to_vec()).unwrap();
+
+  assert_eq!(
+    verifier.add_leaf(7, b"past the end"),
+    Err(VerifyError::OutOfBounds { index: 14 })
+  );
+  for block in &[1 << 62, 1 << 63, u64::MAX] {
+    assert!(matches!(
+      verifier.add_leaf(*block, b"past the end"),
+      Err(VerifyError::OutOfBounds { .. })
+    ));
+  }
+  assert_eq!(
+    verifier.add_leaf(6, b"nope"),
+    Err(VerifyError::Mismatch { index: 12 })
+  );
+
+  verifier.add_leaf(4, b"nope").unwrap();
+  assert_eq!(
+    verifier.add_leaf(4, &data[4]),
+    Err(VerifyError::Conflict { index: 8 })
+  );
+  assert_eq!(
+    verifier.add_node(node(&nodes, 10)),
+    Err(VerifyError::Mismatch { index: 9 })
+  );
+  assert_eq!(verifier.pending().count(), 0);
+
+  verifier.add_leaf(4, &data[4]).unwrap();
+  verifier.add_node(node(&nodes, 10)).unwrap();
+  assert!(verifier.is_trusted(8));
+}
+
+#[test]
+fn verifier_only_trusts_checked_data() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+  let mut verifier = Verifier::new(H, mts.roots().to_vec()).unwrap();
+
+  // An honest hash with tampered data still hashes up to the roots.
+  let mut evil = DefaultNode::clone(&node(&nodes, 0));
+  evil.data = Some(b"EVIL".to_vec());
+  verifier.add_node(Arc::new(evil)).unwrap();
+  verifier.add_node(node(&nodes, 2)).unwrap();
+  verifier.add_node(node(&nodes, 5)).unwrap();
+  assert!(verifier.is_trusted(0));
+  assert!(!verifier.has_block(0));
+  assert!(!verifier.has_block(1));
+
+  assert_eq!(
+    verifier.add_leaf(0, b"EVIL"),
+    Err(VerifyError::Mismatch { index: 0 })
+  );
+  assert!(!verifier.has_block(0));
+
+  verifier.add_leaf(0, &data[0]).unwrap();
+  assert!(verifier.has_block(0));
+  assert_eq!(verifier.get(0).unwrap().data, Some(data[0].clone()));
+
+  // Data that is discarded with its subtree is no longer counted.
+  let mut bad = DefaultNode::clone(&node(&nodes, 10));
+  bad.hash[0] ^= 1;
+  verifier.add_leaf(4, &data[4]).unwrap();
+  assert_eq!(
+    verifier.add_node(Arc::new(bad)),
+    Err(VerifyError::Mismatch { index: 9 })
+  );
+  verifier.add_node(node(&nodes, 8)).unwrap();
+  verifier.add_node(node(&nodes, 10)).unwrap();
+  assert!(verifier.is_trusted(8));
+  assert!(!verifier.has_block(4));
+  assert!(!verifier.has_block(u64::MAX));
+}
+
+#[test]
+fn verifier_clears_pending() {
+  let data = blocks(7);
+  let (mts, nodes) = build_mts(&data);
+  let mut verifier = Verifier::new(H, mts.roots().to_vec()).unwrap();
+
+  verifier.add_leaf(0, &data[0]).unwrap();
+  verifier.add_node(node(&nodes, 2)).unwrap();
+  assert_eq!(verifier.pending_len(), 3);
+
+  verifier.clear_pending();
+  assert_eq!(verifier.pending_len(), 0);
+  assert_eq!(verifier.trusted().count(), 3);
+
+  verifier.add_leaf(0, &data[0]).unwrap();
+  verifier.add_node(node(&nodes, 2)).unwrap();
+  verifier.add_node(node(&nodes, 5)).unwrap();
+  assert!(verifier.is_trusted(0));
+  assert_eq!(verifier.pending_len(), 0);
+}
+
+#[test]
+fn verifier_accepts_any_order() {
+  fn prop(data: Vec<Vec<u8>>, picks: Vec<usize>, shift: usize) -> bool {
+    if data.is_empty() || picks.is_empty() {
+      return true;
+    }
+    let (mts, nodes) = build_mts(&data);
+    let blocks: Vec<u64> = picks
+      .iter()
+      .map(|pick| (pick % data.len()) as u64)
+      .collect();
+    let proof = MultiProof::new(&blocks, mts.roots(), &nodes).unwrap();
+
+    let mut items: Vec<Option<&Arc<DefaultNode>>> =
+      proof.nodes().iter().map(Some).collect();
+    items.extend(proof.blocks().iter().map(|_| None));
+    let shift = shift % items.len();
+    items.rotate_left(shift);
+    if shift % 2 == 1 {
+      items.reverse();
+    }
+
+    let mut verifier = Verifier::new(H, mts.roots().to_vec()).unwrap();
+    let mut leaves = proof.blocks().iter();
+    for item in items {
+      let added = match item {
+        Some(node) => verifier.add_node(Arc::clone(node)),
+        None => {
+          let block = *leaves.next().unwrap();
+          verifier.add_leaf(block, &data[block as usize])
+        }
+      };
+      if added.is_err() {
+        return false;
+      }
+    }
+
+    verifier.pending().count() == 0
+      && proof
+        .blocks()
+        .iter()
+        .all(|block| verifier.is_trusted(2 * block))
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>, Vec<usize>, usize) -> bool);
+}
+
+#[test]
+fn verifier_rejects_tampered_hashes() {
+  fn prop(data: Vec<Vec<u8>>, block: usize, target: usize) -> bool {
+    if data.is_empty() {
+      return true;
+    }
+    let block = (block % data.len()) as u64;
+    let (mts, nodes) = build_mts(&data);
+    let proof = MultiProof::new(&[block], mts.roots(), &nodes).unwrap();
+    if proof.nodes().is_empty() {
+      return true;
+    }
+
+    let mut tampered = proof.nodes().to_vec();
+    let target = target % tampered.len();
+    let mut node = DefaultNode::clone(&tampered[target]);
+    node.hash[0] ^= 1;
+    tampered[target] = Arc::new(node);
+
+    let mut verifier = Verifier::new(H, mts.roots().to_vec()).unwrap();
+    let mut failed = verifier.add_leaf(block, &data[block as usize]).is_err();
+    for node in tampered {
+      failed |=
+        matches!(verifier.add_node(node), Err(VerifyError::Mismatch { .. }));
+    }
+    failed && !verifier.is_trusted(2 * block)
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>, usize, usize) -> bool);
+}
@@ -0,0 +1,167 @@
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{build_mts, leaf_hash, parent_hash};
+use merkle_tree_stream::{
+  HashMethods, MerkleTreeStream, Node, NodeParts, PartialNode, Weight, Weighted,
+};
+use quickcheck::quickcheck;
+use std::convert::TryInto;
+use std::sync::Arc;
+
+/// Byte length, record count and timestamp range of a subtree. Every block
+/// starts with a big-endian `u64` timestamp.
+#[derive(Debug, Clone, PartialEq)]
+struct Stats {
+  bytes: u64,
+  records: u64,
+  min: u64,
+  max: u64,
+}
+
+impl Weight for Stats {
+  fn leaf(data: &[u8]) -> Self {
+    let time = u64::from_be_bytes(data[..8].try_into().unwrap());
+    Stats {
+      bytes: data.len() as u64,
+      records: 1,
+      min: time,
+      max: time,
+    }
+  }
+
+  fn combine(&self, right: &Self) -> Self {
+    Stats {
+      bytes: self.bytes + right.bytes,
+      records: self.records + right.records,
+      min: self.min.min(right.min),
+      max: self.max.max(right.max),
+    }
+  }
+}
+
+#[derive(Debug)]
+struct StatsNode {
+  index: u64,
+  parent: u64,
+  hash: Vec<u8>,
+  stats: Stats,
+}
+
+impl Node for StatsNode {
+  fn len(&self) -> u64 {
+    self.stats.bytes
+  }
+
+  fn is_empty(&self) -> bool {
+    self.stats.bytes == 0
+  }
+
+  fn parent(&self) -> u64 {
+    self.parent
+  }
+
+  fn index(&self) -> u64 {
+    self.index
+  }
+
+  fn hash(&self) -> &[u8] {
+    &self.hash
+  }
+}
+
+impl Weighted<Stats> for StatsNode {
+  fn weight(&self) -> Stats {
+    self.stats.clone()
+  }
+}
+
+impl From<NodeParts<Vec<u8>, Vec<u8>, Stats>> for StatsNode {
+  fn from(parts: NodeParts<Vec<u8>, Vec<u8>, Stats>) -> Self {
+    StatsNode {
+      index: parts.node().index(),
+      parent: parts.node().parent,
+      hash: parts.hash().clone(),
+      stats: parts.node().weight().clone(),
+    }
+  }
+}
+
+#[derive(Debug)]
+struct StatsH;
+impl HashMethods<Vec<u8>, Stats> for StatsH {
+  type Node = StatsNode;
+  type Hash = Vec<u8>;
+
+  fn leaf(
+    &self,
+    leaf: &PartialNode<Vec<u8>, Stats>,
+    _roots: &[Arc<Self::Node>],
+  ) -> Self::Hash {
+    leaf_hash(leaf)
+  }
+
+  fn parent(&self, a: &Self::Node, b: &Self::Node) -> Self::Hash {
+    parent_hash(a, b)
+  }
+}
+
+fn block(time: u64, len: usize) -> Vec<u8> {
+  let mut block = time.to_be_bytes().to_vec();
+  block.resize(8 + len, 0);
+  block
+}
+
+#[test]
+fn parents_combine_weights() {
+  let data = vec![block(5, 0), block(3, 2), block(9, 1)];
+  let mut mts = MerkleTreeStream::new(StatsH, Vec::new());
+  let mut nodes = Vec::new();
+  mts.extend(&data, &mut nodes);
+
+  assert_eq!(nodes[2].index(), 1);
+  assert_eq!(
+    nodes[2].stats,
+    Stats {
+      bytes: 18,
+      records: 2,
+      min: 3,
+      max: 5
+    }
+  );
+  assert_eq!(mts.roots()[1].stats.records, 1);
+}
+
+quickcheck! {
+  fn roots_sum_up_all_blocks(blocks: Vec<(u64, u8)>) -> bool {
+    let data: Vec<Vec<u8>> = blocks
+      .iter()
+      .map(|(time, len)| block(*time, *len as usize))
+      .collect();
+
+    let mut mts = MerkleTreeStream::new(StatsH, Vec::new());
+    let mut nodes = Vec::new();
+    mts.extend(&data, &mut nodes);
+
+    let total = data
+      .iter()
+      .map(|block| Stats::leaf(block))
+      .reduce(|a, b| a.combine(&b));
+    let roots = mts
+      .roots()
+      .iter()
+      .map(|root| Weighted::<Stats>::weight(&**root))
+      .reduce(|a, b| a.combine(&b));
+
+    // The hashes don't depend on the weight.
+    let (reference, reference_nodes) = build_mts(&data);
+    total == roots
+      && mts.blocks() == reference.blocks()
+      && nodes
+        .iter()
+        .zip(&reference_nodes)
+        .all(|(a, b)| a.hash() == b.hash() && a.len() == b.len())
+  }
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "std")]
+
+extern crate merkle_tree_stream;
+extern crate quickcheck;
+
+mod common;
+
+use common::{build_mts, H};
+use merkle_tree_stream::writer::MerkleWriter;
+use merkle_tree_stream::MerkleTreeStream;
+use quickcheck::quickcheck;
+use std::io::{self, Write};
+
+#[test]
+fn writer_hashes_full_blocks() {
+  let mts = MerkleTreeStream::new(H, Vec::new());
+  let mut writer = MerkleWriter::new(mts, Vec::new(), 4);
+
+  writer.write_all(b"hello world").unwrap();
+  assert_eq!(writer.stream().blocks(), 2);
+  assert_eq!(writer.nodes().len(), 3);
+
+  writer.flush().unwrap();
+  assert_eq!(writer.stream().blocks(), 2);
+
+  writer.finish().unwrap();
+  assert_eq!(writer.stream().blocks(), 3);
+  assert_eq!(writer.take_nodes().len(), 4);
+  assert!(writer.nodes().is_empty());
+
+  let (_, inner) = writer.into_parts();
+  assert_eq!(inner, b"hello world");
+}
+
+#[test]
+fn writer_drains_nodes() {
+  let mts = MerkleTreeStream::new(H, Vec::new());
+  let mut writer = MerkleWriter::new(mts, io::sink(), 4);
+
+  let mut drained = 0;
+  for _ in 0..100 {
+    writer.write_all(b"some data").unwrap();
+    drained += writer.drain_nodes().count();
+    assert!(writer.nodes().is_empty());
+  }
+  writer.finish().unwrap();
+  drained += writer.drain_nodes().count();
+
+  let blocks = writer.stream().blocks();
+  assert_eq!(blocks, 225);
+  assert_eq!(
+    drained as u64,
+    2 * blocks - writer.stream().roots().len() as u64
+  );
+}
+
+#[test]
+fn writer_io_copy() {
+  let data = vec![7u8; 10_000];
+  let mts = MerkleTreeStream::new(H, Vec::new());
+  let mut writer = MerkleWriter::new(mts, io::sink(), 1024);
+
+  io::copy(&mut &data[..], &mut writer).unwrap();
+  writer.finish().unwrap();
+
+  let blocks: Vec<Vec<u8>> = data.chunks(1024).map(|c| c.to_vec()).collect();
+  let (mts, nodes) = build_mts(&blocks);
+  assert_eq!(writer.stream().roots(), mts.roots());
+  assert_eq!(writer.nodes(), &nodes[..]);
+}
+
+#[test]
+fn writer_matches_next() {
+  fn prop(writes: Vec<Vec<u8>>, block_size: u8) -> bool {
+    let block_size = 1 + block_size as usize % 16;
+    let data = writes.concat();
+
+    let mts = MerkleTreeStream::new(H, Vec::new());
+    let mut writer = MerkleWriter::new(mts, Vec::new(), block_size);
+    for bytes in &writes {
+      writer.write_all(bytes).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let blocks: Vec<Vec<u8>> =
+      data.chunks(block_size).map(|c| c.to_vec()).collect();
+    let (mts, nodes) = build_mts(&blocks);
+    writer.nodes() == &nodes[..]
+      && writer.stream().roots() == mts.roots()
+      && writer.get_ref() == &data
+  }
+  quickcheck(prop as fn(Vec<Vec<u8>>, u8) -> bool);
+}